@@ -0,0 +1,208 @@
+use image::ImageError;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{fmt, io, path::PathBuf};
+
+use crate::failures::{FileError, Stage};
+use crate::jobs::JobId;
+
+/// Every error the engine can return. Serialized for the frontend as `{ code, message, path }`.
+#[derive(Debug)]
+pub enum AugmentError {
+    MissingDirectories(Vec<&'static str>),
+    InputNotFound(PathBuf),
+    InputEmpty(PathBuf),
+    OutputNotWritable { path: PathBuf, source: io::Error },
+    OutputInsideInput { input: PathBuf, output: PathBuf },
+    OutputIsInput(PathBuf),
+    OutputCollision { output: PathBuf, inputs: Vec<PathBuf> },
+    OutputExists(PathBuf),
+    UnknownTransformation(String),
+    // wszystkie problemy z walidacji naraz
+    Validation(Vec<AugmentError>),
+    InvalidOptions(String),
+    JobNotRunning(JobId),
+    Read { path: PathBuf, source: io::Error },
+    Decode { path: PathBuf, source: ImageError },
+    // przekracza budżet pamięci; 0 gdy budżetu nie ma, a limit zgłosił dekoder
+    ImageTooLarge { path: PathBuf, width: u32, height: u32, needed_mb: usize, budget_mb: usize },
+    Transform { path: PathBuf, transform: String, message: String },
+    Save { path: PathBuf, source: ImageError },
+    Write { path: PathBuf, source: io::Error },
+    Journal { path: PathBuf, source: io::Error },
+    // fail fast: pierwszy błąd pliku przerywa run
+    Stopped(Box<FileError>),
+    Internal(String),
+}
+
+impl AugmentError {
+    pub fn code(&self) -> &'static str {
+        if self.is_disk_full() {
+            return "disk_full";
+        }
+        match self {
+            AugmentError::MissingDirectories(_) => "missing_directories",
+            AugmentError::InputNotFound(_) => "input_not_found",
+            AugmentError::InputEmpty(_) => "input_empty",
+            AugmentError::OutputNotWritable { .. } => "output_not_writable",
+            AugmentError::OutputInsideInput { .. } => "output_inside_input",
+            AugmentError::OutputIsInput(_) => "output_is_input",
+            AugmentError::OutputCollision { .. } => "output_collision",
+            AugmentError::OutputExists(_) => "output_exists",
+            AugmentError::UnknownTransformation(_) => "unknown_transformation",
+            AugmentError::Validation(_) => "validation_failed",
+            AugmentError::InvalidOptions(_) => "invalid_options",
+            AugmentError::JobNotRunning(_) => "job_not_running",
+            AugmentError::Read { .. } => "read_failed",
+            AugmentError::Decode { .. } => "decode_failed",
+            AugmentError::ImageTooLarge { .. } => "image_too_large",
+            AugmentError::Transform { .. } => "transform_failed",
+            AugmentError::Save { .. } => "save_failed",
+            AugmentError::Write { .. } => "write_failed",
+            AugmentError::Journal { .. } => "journal_failed",
+            AugmentError::Stopped(_) => "stopped_on_error",
+            AugmentError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            AugmentError::InputNotFound(path)
+            | AugmentError::InputEmpty(path)
+            | AugmentError::OutputNotWritable { path, .. }
+            | AugmentError::OutputInsideInput { output: path, .. }
+            | AugmentError::OutputIsInput(path)
+            | AugmentError::OutputCollision { output: path, .. }
+            | AugmentError::OutputExists(path)
+            | AugmentError::Read { path, .. }
+            | AugmentError::Decode { path, .. }
+            | AugmentError::ImageTooLarge { path, .. }
+            | AugmentError::Transform { path, .. }
+            | AugmentError::Save { path, .. }
+            | AugmentError::Write { path, .. }
+            | AugmentError::Journal { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    // etap przetwarzania pojedynczego pliku, None dla błędów całego runu
+    pub fn stage(&self) -> Option<Stage> {
+        match self {
+            AugmentError::Read { .. } => Some(Stage::Read),
+            AugmentError::Decode { .. } | AugmentError::ImageTooLarge { .. } => Some(Stage::Decode),
+            AugmentError::Transform { .. } => Some(Stage::Transform),
+            AugmentError::Save { .. } | AugmentError::Write { .. } | AugmentError::OutputExists(_) => {
+                Some(Stage::Save)
+            }
+            AugmentError::Journal { .. } => Some(Stage::Journal),
+            _ => None,
+        }
+    }
+
+    pub fn transform(&self) -> Option<&str> {
+        match self {
+            AugmentError::Transform { transform, .. } => Some(transform),
+            _ => None,
+        }
+    }
+
+    fn is_disk_full(&self) -> bool {
+        let io_error = match self {
+            AugmentError::Write { source, .. } | AugmentError::Journal { source, .. } => Some(source),
+            AugmentError::Save { source: ImageError::IoError(source), .. } => Some(source),
+            _ => None,
+        };
+        io_error.is_some_and(|e| e.kind() == io::ErrorKind::StorageFull)
+    }
+}
+
+impl fmt::Display for AugmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AugmentError::MissingDirectories(missing) => {
+                write!(f, "Directories {} aren't set.", missing.join(", "))
+            }
+            AugmentError::InputNotFound(path) => {
+                write!(f, "Input directory {} doesn't exist.", path.display())
+            }
+            AugmentError::InputEmpty(path) => {
+                write!(f, "Input directory {} doesn't contain any PNG images.", path.display())
+            }
+            AugmentError::OutputNotWritable { path, source } => {
+                write!(f, "Output directory {} isn't writable: {source}", path.display())
+            }
+            AugmentError::OutputInsideInput { input, output } => write!(
+                f,
+                "Output directory {} is inside the input directory {}. Confirm writing into the input directory to continue.",
+                output.display(),
+                input.display()
+            ),
+            AugmentError::OutputIsInput(path) => write!(
+                f,
+                "Output directory {} is the input directory. Confirm writing into the input directory to continue.",
+                path.display()
+            ),
+            AugmentError::OutputCollision { output, inputs } => {
+                let inputs: Vec<String> = inputs.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "Inputs {} would all be written to {}.", inputs.join(", "), output.display())
+            }
+            AugmentError::OutputExists(path) => write!(f, "Output {} already exists.", path.display()),
+            AugmentError::UnknownTransformation(name) => {
+                write!(f, "Transformation '{name}' doesn't exist.")
+            }
+            AugmentError::Validation(problems) => {
+                let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+                write!(f, "{}", messages.join(" "))
+            }
+            AugmentError::InvalidOptions(message) => write!(f, "{message}"),
+            AugmentError::JobNotRunning(id) => write!(f, "Augmentation job {id} isn't running."),
+            AugmentError::Read { path, source } => {
+                write!(f, "Failed to read {}: {source}", path.display())
+            }
+            AugmentError::Decode { path, source } => {
+                write!(f, "Failed to decode {}: {source}", path.display())
+            }
+            AugmentError::ImageTooLarge { path, width, height, needed_mb, budget_mb: 0 } => write!(
+                f,
+                "{} is {width}×{height} and needs about {needed_mb} MB, more than the decoder can allocate.",
+                path.display()
+            ),
+            AugmentError::ImageTooLarge { path, width, height, needed_mb, budget_mb } => write!(
+                f,
+                "{} is {width}×{height} and needs about {needed_mb} MB, more than the {budget_mb} MB memory budget. \
+                 Raise the budget or downscale the image.",
+                path.display()
+            ),
+            AugmentError::Transform { path, transform, message } => {
+                write!(f, "Transformation '{transform}' failed on {}: {message}", path.display())
+            }
+            AugmentError::Save { path, source } => {
+                write!(f, "Failed to save {}: {source}", path.display())
+            }
+            AugmentError::Write { path, source } => {
+                write!(f, "Failed to write {}: {source}", path.display())
+            }
+            AugmentError::Journal { path, source } => {
+                write!(f, "Failed to update the run journal in {}: {source}", path.display())
+            }
+            AugmentError::Stopped(failure) => {
+                write!(f, "Augmentation stopped on the first error: {failure}")
+            }
+            AugmentError::Internal(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for AugmentError {}
+
+impl Serialize for AugmentError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AugmentError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("path", &self.path().map(|p| p.display().to_string()))?;
+        if let AugmentError::Validation(problems) = self {
+            state.serialize_field("problems", problems)?;
+        }
+        state.end()
+    }
+}
@@ -0,0 +1,253 @@
+use image::{
+    io::{Limits, Reader},
+    DynamicImage, ImageError,
+};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Cursor,
+    path::Path,
+    sync::{Condvar, Mutex},
+};
+
+use crate::error::AugmentError;
+use crate::metadata;
+use crate::plan::PlannedImage;
+
+// w trybie adaptacyjnym: piksele wejść razy liczba wyjść na jedno zadanie
+const ADAPTIVE_CHUNK_WORK: u64 = 256_000_000;
+const MAX_ADAPTIVE_CHUNK: usize = 64;
+// gdy nagłówek nie mówi, jak obraz się zdekoduje: najgorszy przypadek, RGBA 16-bit
+const MAX_DECODED_BYTES_PER_PIXEL: usize = 8;
+// robocza kopia RGBA8 w transformacji
+const WORKING_COPY_BYTES_PER_PIXEL: usize = 4;
+const MB: usize = 1024 * 1024;
+
+// Jak wykonujemy run, bez wpływu na to, co powstaje
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExecutionOptions {
+    // przerwij cały run na pierwszym błędzie pliku
+    pub fail_fast: bool,
+    // None: wszystkie rdzenie poza jednym, który zostaje dla UI/systemu
+    pub threads: Option<usize>,
+    // obrazów na zadanie, gdy nie dobieramy ich adaptacyjnie
+    pub chunk_size: usize,
+    // dobiera wielkość zadań do rozmiarów obrazów
+    pub adaptive_chunks: bool,
+    // None bez limitu; przydatne na dyskach sieciowych
+    pub max_concurrent_reads: Option<usize>,
+    pub max_concurrent_writes: Option<usize>,
+    // pamięć na obrazy przetwarzane naraz; None bez limitu
+    pub memory_budget_mb: Option<usize>,
+}
+
+impl Default for ExecutionOptions {
+    fn default() -> Self {
+        Self {
+            fail_fast: false,
+            threads: None,
+            chunk_size: 8,
+            adaptive_chunks: false,
+            max_concurrent_reads: None,
+            max_concurrent_writes: None,
+            memory_budget_mb: None,
+        }
+    }
+}
+
+impl ExecutionOptions {
+    pub fn validate(&self) -> Result<(), AugmentError> {
+        let zero = [
+            ("Thread count", self.threads),
+            ("Chunk size", Some(self.chunk_size)),
+            ("Concurrent reads", self.max_concurrent_reads),
+            ("Concurrent writes", self.max_concurrent_writes),
+            ("Memory budget", self.memory_budget_mb),
+        ]
+        .into_iter()
+        .find(|(_, value)| *value == Some(0));
+        match zero {
+            Some((name, _)) => Err(AugmentError::InvalidOptions(format!("{name} must be at least 1."))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn thread_pool(&self) -> Result<ThreadPool, AugmentError> {
+        let num_threads = self.threads.unwrap_or_else(|| num_cpus::get().saturating_sub(1)).max(1);
+        ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| AugmentError::Internal(format!("Failed to build rayon pool: {e}")))
+    }
+
+    /// Splits the plan into work units. Adaptive chunks hold about the same number of pixels to write,
+    /// read from image headers, so a few huge images don't end up in one chunk while small ones
+    /// are handed out one by one. Call inside the run's pool: headers are read in parallel.
+    pub fn chunks<'a>(&self, images: &'a [PlannedImage], io: &IoLimits) -> Vec<&'a [PlannedImage]> {
+        let chunk_size = self.chunk_size.max(1);
+        if !self.adaptive_chunks {
+            return images.chunks(chunk_size).collect();
+        }
+
+        let work: Vec<u64> = images
+            .par_iter()
+            .map(|image| {
+                let _read = io.reads.acquire();
+                // nieczytelny nagłówek i tak skończy się błędem pliku, liczymy go jak zwykły obraz
+                let pixels = image::image_dimensions(&image.path)
+                    .map(|(w, h)| w as u64 * h as u64)
+                    .unwrap_or(ADAPTIVE_CHUNK_WORK / chunk_size as u64);
+                pixels * (image.outputs as u64 + 1)
+            })
+            .collect();
+
+        let mut chunks = Vec::new();
+        let (mut start, mut total) = (0, 0);
+        for (i, &w) in work.iter().enumerate() {
+            if i > start && (total + w > ADAPTIVE_CHUNK_WORK || i - start == MAX_ADAPTIVE_CHUNK) {
+                chunks.push(&images[start..i]);
+                (start, total) = (i, 0);
+            }
+            total += w;
+        }
+        if start < images.len() {
+            chunks.push(&images[start..]);
+        }
+        chunks
+    }
+
+    pub fn io_limits(&self) -> IoLimits {
+        IoLimits {
+            reads: Throttle::new(self.max_concurrent_reads),
+            writes: Throttle::new(self.max_concurrent_writes),
+        }
+    }
+
+    pub fn memory_budget(&self) -> MemoryBudget {
+        MemoryBudget::new(self.memory_budget_mb)
+    }
+}
+
+pub struct IoLimits {
+    pub reads: Throttle,
+    pub writes: Throttle,
+}
+
+/// Limits how many pixels are decoded and transformed at once, so parallel workers
+/// on huge images wait for each other instead of exhausting RAM.
+pub struct MemoryBudget {
+    budget_mb: Option<usize>,
+    throttle: Throttle,
+}
+
+impl MemoryBudget {
+    pub fn new(budget_mb: Option<usize>) -> Self {
+        MemoryBudget { budget_mb, throttle: Throttle::new(budget_mb.map(|mb| mb.saturating_mul(MB))) }
+    }
+
+    /// Decodes `bytes` once the image fits in the budget. The permit must be held
+    /// until the image and everything derived from it is dropped.
+    pub fn decode(&self, path: &Path, bytes: &[u8]) -> Result<(DynamicImage, Permit<'_>), AugmentError> {
+        let decode_error = |source| AugmentError::Decode { path: path.to_path_buf(), source };
+        let reader = || {
+            Reader::new(Cursor::new(bytes))
+                .with_guessed_format()
+                .map_err(|source| decode_error(ImageError::IoError(source)))
+        };
+
+        // wymiary i typ pikseli z samego nagłówka, zanim cokolwiek zaalokujemy
+        let (width, height) = reader()?.into_dimensions().map_err(decode_error)?;
+        let decoded_bpp = metadata::read_header(Cursor::new(bytes))
+            .map_or(MAX_DECODED_BYTES_PER_PIXEL, |header| header.bytes_per_pixel);
+        let needed = bytes_in_flight(width, height, decoded_bpp);
+        let permit = self.reserve(path, width, height, needed)?;
+
+        let mut reader = reader()?;
+        reader.limits(self.decode_limits());
+        match reader.decode() {
+            Ok(img) => Ok((img, permit)),
+            Err(ImageError::Limits(_)) => Err(self.too_large(path, width, height, needed)),
+            Err(source) => Err(decode_error(source)),
+        }
+    }
+
+    /// Blocks until `needed` bytes fit next to the images already in flight. Fails right away for an image
+    /// that doesn't fit in the budget at all. Hold the permit until the image's outputs are saved.
+    fn reserve(&self, path: &Path, width: u32, height: u32, needed: usize) -> Result<Permit<'_>, AugmentError> {
+        if let Some(budget_mb) = self.budget_mb {
+            if needed > budget_mb.saturating_mul(MB) {
+                return Err(self.too_large(path, width, height, needed));
+            }
+        }
+        Ok(self.throttle.acquire_many(needed))
+    }
+
+    /// Decoder limits matching the budget, so a decoder never allocates more than one image may use.
+    fn decode_limits(&self) -> Limits {
+        let mut limits = Limits::no_limits();
+        limits.max_alloc = self.budget_mb.map(|mb| mb.saturating_mul(MB) as u64);
+        limits
+    }
+
+    fn too_large(&self, path: &Path, width: u32, height: u32, needed: usize) -> AugmentError {
+        AugmentError::ImageTooLarge {
+            path: path.to_path_buf(),
+            width,
+            height,
+            needed_mb: needed.div_ceil(MB),
+            budget_mb: self.budget_mb.unwrap_or(0),
+        }
+    }
+}
+
+// Szczyt na obraz: oryginał, baza wariantu i wynik w zdekodowanym formacie plus robocza kopia RGBA8
+fn bytes_in_flight(width: u32, height: u32, decoded_bpp: usize) -> usize {
+    let per_pixel = 3 * decoded_bpp + WORKING_COPY_BYTES_PER_PIXEL;
+    (width as usize).saturating_mul(height as usize).saturating_mul(per_pixel)
+}
+
+/// Counting semaphore: permits taken at once never add up to more than `limit`.
+pub struct Throttle {
+    limit: Option<usize>,
+    in_use: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Throttle {
+    pub fn new(limit: Option<usize>) -> Self {
+        Throttle { limit, in_use: Mutex::new(0), released: Condvar::new() }
+    }
+
+    /// Blocks until a permit is free. The permit is returned when dropped.
+    pub fn acquire(&self) -> Permit<'_> {
+        self.acquire_many(1)
+    }
+
+    /// Like `acquire`, for `amount` units at once. More than `limit` waits for everything to be free.
+    pub fn acquire_many(&self, amount: usize) -> Permit<'_> {
+        let Some(limit) = self.limit else { return Permit { throttle: self, amount: 0 } };
+        let amount = amount.min(limit);
+        let mut in_use = self.in_use.lock().unwrap();
+        while *in_use + amount > limit {
+            in_use = self.released.wait(in_use).unwrap();
+        }
+        *in_use += amount;
+        Permit { throttle: self, amount }
+    }
+}
+
+pub struct Permit<'a> {
+    throttle: &'a Throttle,
+    amount: usize,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.amount > 0 {
+            *self.throttle.in_use.lock().unwrap() -= self.amount;
+            // zwolnione miejsce może wystarczyć kilku mniejszym obrazom
+            self.throttle.released.notify_all();
+        }
+    }
+}
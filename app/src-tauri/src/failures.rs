@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::error::AugmentError;
+
+const ERRORS_FILE: &str = "errors.log";
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Read,
+    Decode,
+    Transform,
+    Save,
+    Journal,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileError {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stage: Option<Stage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transform: Option<String>,
+    code: &'static str,
+    message: String,
+}
+
+impl From<AugmentError> for FileError {
+    fn from(error: AugmentError) -> Self {
+        FileError {
+            path: error.path().map(|p| p.display().to_string()).unwrap_or_default(),
+            stage: error.stage(),
+            transform: error.transform().map(str::to_string),
+            code: error.code(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// `errors.log` in the output directory, one JSON line per failure. Created on the first failure.
+pub struct FailureLog {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    failures: Mutex<Vec<FileError>>,
+}
+
+impl FailureLog {
+    pub fn new(output_dir: &Path) -> io::Result<Self> {
+        let path = output_dir.join(ERRORS_FILE);
+        // log z poprzedniego uruchomienia nie dotyczy tego
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        Ok(FailureLog {
+            path,
+            file: Mutex::new(None),
+            failures: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn record(&self, failure: &FileError) {
+        self.failures.lock().unwrap().push(failure.clone());
+
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            *file = File::create(&self.path).ok();
+        }
+        if let (Some(file), Ok(line)) = (file.as_mut(), serde_json::to_string(failure)) {
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    pub fn first(&self) -> Option<FileError> {
+        self.failures.lock().unwrap().first().cloned()
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+use crate::error::AugmentError;
+
+pub type JobId = u64;
+
+// Stan współdzielony między komendami a wątkami roboczymi
+#[derive(Default)]
+pub struct JobControl {
+    cancelled: AtomicBool,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl JobControl {
+    pub fn cancel(&self) {
+        // pod blokadą, żeby wątek czekający w wait_while_paused nie przegapił wybudzenia
+        let _paused = self.paused.lock().unwrap();
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.resumed.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn pause(&self) {
+        *self.paused.lock().unwrap() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.paused.lock().unwrap() = false;
+        self.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    /// Blocks the calling worker until the job is resumed or cancelled.
+    pub fn wait_while_paused(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused && !self.is_cancelled() {
+            paused = self.resumed.wait(paused).unwrap();
+        }
+    }
+}
+
+/// Running augmentation jobs, managed as tauri state.
+#[derive(Default)]
+pub struct Jobs {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<JobId, Arc<JobControl>>>,
+}
+
+impl Jobs {
+    pub fn start(&self) -> (JobId, Arc<JobControl>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let control = Arc::new(JobControl::default());
+        self.running.lock().unwrap().insert(id, control.clone());
+        (id, control)
+    }
+
+    pub fn get(&self, id: JobId) -> Result<Arc<JobControl>, AugmentError> {
+        self.running
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or(AugmentError::JobNotRunning(id))
+    }
+
+    pub fn finish(&self, id: JobId) {
+        self.running.lock().unwrap().remove(&id);
+    }
+}
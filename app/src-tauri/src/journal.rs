@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const JOURNAL_FILE: &str = ".augmentator-journal.jsonl";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub input: String,
+    pub input_hash: String,
+    pub recipe_hash: String,
+    // względem katalogu wyjściowego
+    pub outputs: Vec<String>,
+}
+
+// Wyjście zapisywane właśnie dla obrazu, który jeszcze nie ma wpisu ukończenia
+#[derive(Serialize, Deserialize)]
+struct PendingOutput {
+    input: String,
+    pending: String,
+}
+
+/// Append-only record of finished images, one JSON line each, kept in the output directory.
+/// Outputs are also recorded right before they're written, so a crash mid-image doesn't leave
+/// files nobody knows about.
+pub struct Journal {
+    output_dir: PathBuf,
+    entries: HashMap<String, JournalEntry>,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    pub fn open(output_dir: &Path) -> io::Result<Self> {
+        let path = output_dir.join(JOURNAL_FILE);
+
+        let (entries, pending) = load(&path)?;
+
+        // kompaktujemy przy każdym otwarciu, żeby dziennik nie rósł z każdym uruchomieniem;
+        // zostają tylko oczekujące wyjścia, których nie obejmuje wpis ukończenia
+        let temp_path = output_dir.join(format!("{JOURNAL_FILE}.tmp"));
+        let mut compacted = File::create(&temp_path)?;
+        for entry in entries.values() {
+            writeln!(compacted, "{}", serde_json::to_string(entry)?)?;
+        }
+        for (input, outputs) in pending {
+            let finished = entries.get(&input).map(|e| e.outputs.as_slice()).unwrap_or_default();
+            for output in outputs.into_iter().filter(|o| !finished.contains(o)) {
+                let line = PendingOutput { input: input.clone(), pending: output };
+                writeln!(compacted, "{}", serde_json::to_string(&line)?)?;
+            }
+        }
+        compacted.sync_all()?;
+        fs::rename(&temp_path, &path)?;
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+        Ok(Journal {
+            output_dir: output_dir.to_path_buf(),
+            entries,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Outputs recorded by earlier runs, finished or not, relative to the output directory.
+    pub fn recorded_outputs(output_dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let (entries, pending) = load(&output_dir.join(JOURNAL_FILE))?;
+        let outputs: BTreeSet<String> = entries
+            .into_values()
+            .flat_map(|e| e.outputs)
+            .chain(pending.into_values().flatten())
+            .collect();
+        Ok(outputs.into_iter().map(PathBuf::from).collect())
+    }
+
+    pub fn is_up_to_date(&self, input: &str, input_hash: &str, recipe_hash: &str) -> bool {
+        self.entries.get(input).is_some_and(|entry| {
+            entry.input_hash == input_hash
+                && entry.recipe_hash == recipe_hash
+                && entry.outputs.iter().all(|o| self.output_dir.join(o).is_file())
+        })
+    }
+
+    /// Outputs of `input` when it last finished, relative to the output directory.
+    pub fn finished_outputs(&self, input: &str) -> &[String] {
+        self.entries.get(input).map(|e| e.outputs.as_slice()).unwrap_or_default()
+    }
+
+    pub fn record(&self, entry: &JournalEntry) -> io::Result<()> {
+        self.append(serde_json::to_string(entry)?)
+    }
+
+    /// Records `output` (relative to the output directory) before it's written.
+    pub fn record_pending(&self, input: &str, output: &str) -> io::Result<()> {
+        let line = PendingOutput { input: input.to_string(), pending: output.to_string() };
+        self.append(serde_json::to_string(&line)?)
+    }
+
+    fn append(&self, line: String) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        file.write_all(format!("{line}\n").as_bytes())?;
+        file.flush()
+    }
+}
+
+type Pending = HashMap<String, BTreeSet<String>>;
+
+// Ostatni wpis dla danego wejścia wygrywa, urwana linia po crashu jest pomijana
+fn load(path: &Path) -> io::Result<(HashMap<String, JournalEntry>, Pending)> {
+    let mut entries = HashMap::new();
+    let mut pending: Pending = HashMap::new();
+    if let Ok(file) = File::open(path) {
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) {
+                entries.insert(entry.input.clone(), entry);
+            } else if let Ok(output) = serde_json::from_str::<PendingOutput>(&line) {
+                pending.entry(output.input).or_default().insert(output.pending);
+            }
+        }
+    }
+    Ok((entries, pending))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("augmentator-journal-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(input: &str, input_hash: &str, outputs: &[&str]) -> JournalEntry {
+        JournalEntry {
+            input: input.to_string(),
+            input_hash: input_hash.to_string(),
+            recipe_hash: "recipe".to_string(),
+            outputs: outputs.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn up_to_date_needs_same_hashes_and_existing_outputs() {
+        let dir = temp_dir("up-to-date");
+        Journal::open(&dir).unwrap().record(&entry("cat.png", "h1", &["cat_mirror.png"])).unwrap();
+
+        let journal = Journal::open(&dir).unwrap();
+        assert!(!journal.is_up_to_date("cat.png", "h1", "recipe"), "output missing");
+
+        fs::write(dir.join("cat_mirror.png"), b"").unwrap();
+        assert!(journal.is_up_to_date("cat.png", "h1", "recipe"));
+        assert!(!journal.is_up_to_date("cat.png", "h2", "recipe"));
+        assert!(!journal.is_up_to_date("cat.png", "h1", "other"));
+        assert!(!journal.is_up_to_date("dog.png", "h1", "recipe"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compaction_keeps_last_entry_and_uncovered_pending_outputs() {
+        let dir = temp_dir("compaction");
+        {
+            let journal = Journal::open(&dir).unwrap();
+            journal.record_pending("cat.png", "cat_mirror.png").unwrap();
+            journal.record_pending("cat.png", "cat_blur.png").unwrap();
+            journal.record(&entry("cat.png", "old", &["cat_mirror.png"])).unwrap();
+            journal.record(&entry("cat.png", "new", &["cat_mirror.png"])).unwrap();
+            journal.record_pending("dog.png", "dog_mirror.png").unwrap();
+        }
+        // urwana linia po crashu
+        let mut file = OpenOptions::new().append(true).open(dir.join(JOURNAL_FILE)).unwrap();
+        file.write_all(b"{\"input\":\"cat.png\",\"inp").unwrap();
+        drop(file);
+
+        let journal = Journal::open(&dir).unwrap();
+        assert_eq!(journal.finished_outputs("cat.png"), ["cat_mirror.png"]);
+        assert_eq!(journal.entries["cat.png"].input_hash, "new");
+        drop(journal);
+
+        let lines = fs::read_to_string(dir.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(lines.lines().count(), 3, "{lines}");
+        assert_eq!(
+            Journal::recorded_outputs(&dir).unwrap(),
+            ["cat_blur.png", "cat_mirror.png", "dog_mirror.png"].map(PathBuf::from)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,29 +1,58 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod error;
+mod execution;
+mod failures;
+mod jobs;
+mod journal;
+mod metadata;
+mod phash;
+mod plan;
+mod preview;
+mod provenance;
+mod report;
+mod rules;
+mod run_record;
+mod seeding;
 mod transformation_factory;
 mod transformations;
 
 use blake3::Hasher;
-use image::DynamicImage;
+use image::{DynamicImage, ImageError, ImageFormat};
 use rand::{rngs::StdRng, SeedableRng};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager, State};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::OsString,
     fs, io,
     path::{Path, PathBuf},
 };
 use tauri::webview::WebviewWindow;
 
 use rayon::prelude::*;
-use rayon::ThreadPoolBuilder;
 use std::time::Duration;
 use std::sync::{
     atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc,
 };
 
+use crate::error::AugmentError;
+use crate::execution::{ExecutionOptions, IoLimits, MemoryBudget};
+use crate::failures::{FailureLog, FileError};
+use crate::jobs::{JobControl, JobId, Jobs};
+use crate::journal::{Journal, JournalEntry};
+use crate::metadata::EmbeddedProvenance;
+use crate::plan::*;
+use crate::preview::{Preview, DEFAULT_PREVIEW_SIZE};
+use crate::provenance::{AppliedTransform, Manifest, ProvenanceEntry, APP_VERSION};
+use crate::report::{ReportOptions, ReportStats};
+use crate::rules::TransformRule;
+use crate::run_record::{ReproductionReport, RunRecord};
+use crate::seeding::{derive_seed, SeedScheme};
 use crate::transformation_factory::*;
+use crate::transformations::Params;
 
 
 #[derive(Clone, Serialize)]
@@ -32,6 +61,7 @@ struct AugmentProgress {
     processed: usize,
     total: usize,
     percent: u8,
+    paused: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,150 +70,633 @@ struct Directories {
     output: String,
 }
 
-type AnyErr = Box<dyn std::error::Error + Send + Sync + 'static>;
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AugmentOptions {
+    // ile wariantów każdej transformacji generujemy na obraz
+    variants_per_image: u32,
+    // docelowa liczba obrazów w wyjściu, nadpisuje variants_per_image
+    target_total: Option<usize>,
+    // wyrównanie klas, nadpisuje oba powyższe
+    balance: Option<BalanceOptions>,
+    rules: Vec<TransformRule>,
+    // podział train/val/test, augmentujemy tylko train
+    split: Option<SplitOptions>,
+    // potwierdzenie zapisu do katalogu wejściowego lub jego podkatalogu
+    write_into_input: bool,
+    // co zrobić, gdy plik wyjściowy już istnieje
+    on_conflict: ConflictPolicy,
+    // V1 odtwarza runy sprzed seedów opartych na ścieżce
+    seeding: SeedScheme,
+    // raport HTML i arkusz miniatur po zakończonym runie
+    report: Option<ReportOptions>,
+    #[serde(flatten)]
+    execution: ExecutionOptions,
+}
+
+impl Default for AugmentOptions {
+    fn default() -> Self {
+        Self {
+            variants_per_image: 1,
+            target_total: None,
+            balance: None,
+            rules: Vec::new(),
+            split: None,
+            write_into_input: false,
+            on_conflict: ConflictPolicy::default(),
+            seeding: SeedScheme::default(),
+            report: None,
+            execution: ExecutionOptions::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    // zapis pod pierwszą wolną nazwą `name-2.png`, `name-3.png`, ...
+    Rename,
+    Fail,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunSummary {
+    processed: usize,
+    // aktualne według dziennika, pominięte
+    skipped: usize,
+    // obrazy z błędem, nie liczba wpisów w errors.log
+    failed: usize,
+}
+
+enum Outcome {
+    // wpisy manifestu dla wszystkiego, co ten run zapisał lub zachował
+    Finished { run: RunSummary, entries: Vec<ProvenanceEntry> },
+    Cancelled { processed: usize },
+}
+
+enum Processed {
+    Written,
+    Skipped,
+}
+
+#[derive(Clone)]
+struct WindowEvents {
+    app: AppHandle,
+    label: String,
+}
+
+impl WindowEvents {
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let _ = self.app.emit_to(&self.label, event, payload);
+    }
+}
+
+// Co robimy z każdym obrazem
+struct Recipe {
+    transformations: Vec<String>,
+    rules: Vec<TransformRule>,
+    seed: u64,
+    seeding: SeedScheme,
+    numbered: bool,
+    on_conflict: ConflictPolicy,
+}
+
+impl Recipe {
+    fn resolve(&self, relative_path: &Path, class: &str) -> (Vec<&str>, Vec<&str>) {
+        let active = rules::resolve(&self.transformations, &self.rules, relative_path, class);
+        split_transformations(&active)
+    }
+}
+
+// Jeden run; przy odtwarzaniu z zapisem, z którym porównujemy wynik
+struct RunRequest {
+    directories: Directories,
+    transformations: Vec<String>,
+    seed: u64,
+    options: AugmentOptions,
+    expected: Option<Recorded>,
+}
+
+// Odtwarzany run: jego zapis i katalog z wynikami oraz provenance.jsonl
+struct Recorded {
+    record: RunRecord,
+    output_dir: PathBuf,
+}
 
 #[tauri::command]
 async fn augment_dataset(
     app: AppHandle,
     window: WebviewWindow,
+    jobs: State<'_, Jobs>,
+    directories: Directories,
+    transformations: Vec<String>,
+    seed: u64,
+    options: Option<AugmentOptions>,
+) -> Result<JobId, AugmentError> {
+    let request = RunRequest {
+        directories,
+        transformations,
+        seed,
+        options: options.unwrap_or_default(),
+        expected: None,
+    };
+    start_run(app, window, &jobs, request).await
+}
+
+/// Re-runs the run recorded in `recorded_output` into the empty directory `output` and compares
+/// every output hash.
+#[tauri::command]
+async fn reproduce_augmentation(
+    app: AppHandle,
+    window: WebviewWindow,
+    jobs: State<'_, Jobs>,
+    recorded_output: String,
+    output: String,
+) -> Result<JobId, AugmentError> {
+    let recorded_dir = Path::new(recorded_output.trim());
+    let record = RunRecord::load(recorded_dir)?;
+    if resolve_path(Path::new(output.trim())) == resolve_path(recorded_dir) {
+        return Err(AugmentError::InvalidOptions(
+            "Choose an output directory other than the recorded run's to reproduce it.".to_string(),
+        ));
+    }
+    // dziennik i onConflict pominęłyby gotowe pliki, a ich hashe nic by nie dowodziły
+    let output_dir = Path::new(output.trim());
+    if fs::read_dir(output_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(AugmentError::InvalidOptions(format!(
+            "Reproduce into an empty directory, {} already contains files.",
+            output_dir.display()
+        )));
+    }
+
+    let request = RunRequest {
+        directories: Directories { input: record.input.clone(), output },
+        transformations: record.transformations.clone(),
+        seed: record.seed,
+        options: AugmentOptions { seeding: record.seeding, ..record.options.clone() },
+        expected: Some(Recorded { record, output_dir: recorded_dir.to_path_buf() }),
+    };
+    start_run(app, window, &jobs, request).await
+}
+
+/// Lists everything a run would produce without decoding or writing any image.
+#[tauri::command]
+async fn plan_augmentation(
     directories: Directories,
     transformations: Vec<String>,
     seed: u64,
-) -> Result<String, String> {
-    check_missing_directories(&directories)?;
+    options: Option<AugmentOptions>,
+) -> Result<DryRun, AugmentError> {
+    let options = options.unwrap_or_default();
+    let prepared = prepare_run(&directories, transformations, seed, &options, true).await?;
+    let approximate_splits = options.split.as_ref().is_some_and(|s| s.duplicate_threshold.is_some());
+
+    tauri::async_runtime::spawn_blocking(move || dry_run(&prepared, approximate_splits))
+        .await
+        .map_err(|e| AugmentError::Internal(e.to_string()))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRun {
+    summary: PlanSummary,
+    images: Vec<DryRunImage>,
+    total_outputs: usize,
+    // z wymiarów i typu pikseli w nagłówkach źródeł, bo wyjścia to PNG o tych samych wymiarach
+    estimated_bytes: u64,
+    collisions: Vec<AugmentError>,
+    // już istnieją, o losie decyduje onConflict
+    existing: Vec<String>,
+    // prawie-duplikaty wymagają dekodowania, więc bez nich podział może się różnić od runu
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    approximate_splits: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRunImage {
+    input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split: Option<Split>,
+    outputs: Vec<String>,
+}
+
+fn dry_run(prepared: &PreparedRun, approximate_splits: bool) -> DryRun {
+    let mut images = Vec::new();
+    let mut existing = Vec::new();
+    let mut estimated_bytes = 0;
+
+    for (image, outputs) in prepared.outputs() {
+        // przy zapisie w miejscu oryginał nie jest kopiowany
+        let outputs: Vec<PathBuf> = outputs.into_iter().filter(|out| path_key(out) != path_key(&image.path)).collect();
+        estimated_bytes += estimated_png_size(&image.path) * outputs.len() as u64;
+
+        let relative = |path: &Path, dir: &Path| path.strip_prefix(dir).unwrap_or(path).to_string_lossy().into_owned();
+        let outputs: Vec<String> = outputs
+            .iter()
+            .inspect(|out| {
+                if out.exists() {
+                    existing.push(relative(out, &prepared.output_dir));
+                }
+            })
+            .map(|out| relative(out, &prepared.output_dir))
+            .collect();
+        images.push(DryRunImage { input: relative(&image.path, &prepared.input_dir), split: image.split, outputs });
+    }
+
+    DryRun {
+        summary: prepared.plan.summary(),
+        total_outputs: images.iter().map(|i| i.outputs.len()).sum(),
+        images,
+        estimated_bytes,
+        collisions: output_collisions(prepared),
+        existing,
+        approximate_splits,
+    }
+}
+
+// Typowy PNG zajmuje około 60% surowych pikseli; nieczytelny nagłówek liczymy jako 0
+fn estimated_png_size(path: &Path) -> u64 {
+    const PNG_SIZE_RATIO: f64 = 0.6;
+    let header = fs::File::open(path).ok().and_then(|file| metadata::read_header(io::BufReader::new(file)).ok());
+    header.map_or(0, |h| {
+        let raw = h.width as u64 * h.height as u64 * h.bytes_per_pixel as u64;
+        (raw as f64 * PNG_SIZE_RATIO) as u64
+    })
+}
+
+// Wejścia, plan i przepis, wspólne dla runu i planu na sucho
+struct PreparedRun {
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    plan: Plan,
+    recipe: Recipe,
+}
+
+impl PreparedRun {
+    /// Every planned image with the files it would write.
+    fn outputs(&self) -> impl Iterator<Item = (&PlannedImage, Vec<PathBuf>)> + '_ {
+        self.plan.images.iter().map(|image| {
+            let relative_path = image.path.strip_prefix(&self.input_dir).unwrap_or(&image.path);
+            (image, planned_outputs(image, relative_path, &self.output_dir, &self.recipe))
+        })
+    }
+}
+
+/// In a `dry_run` nothing is written or decoded, so near-duplicates aren't grouped for the split.
+async fn prepare_run(
+    directories: &Directories,
+    transformations: Vec<String>,
+    seed: u64,
+    options: &AugmentOptions,
+    dry_run: bool,
+) -> Result<PreparedRun, AugmentError> {
+    let (image_paths, mut problems) = validate_run(directories, &transformations, options, dry_run);
+    if image_paths.is_empty() {
+        return Err(AugmentError::Validation(problems));
+    }
 
     let input_dir = PathBuf::from(directories.input.trim());
     let output_dir = PathBuf::from(directories.output.trim());
 
-    let image_paths = collect_image_paths(&input_dir).map_err(|e| e.to_string())?;
-    let total = image_paths.len();
+    let mut recipe = Recipe {
+        transformations,
+        rules: options.rules.clone(),
+        seed,
+        seeding: options.seeding,
+        numbered: false,
+        on_conflict: options.on_conflict,
+    };
+
+    let mut plan = Plan::new(image_paths, &input_dir, |relative_path, class| {
+        recipe.resolve(relative_path, class).1.len()
+    });
+    // Błędne opcje pomijamy, żeby policzyć plan bez nich i zgłosić kolizje nazw razem z resztą problemów
+    let fail = |problems: &mut Vec<AugmentError>, err| {
+        problems.push(err);
+        AugmentError::Validation(std::mem::take(problems))
+    };
+    if let Some(split) = options.split.as_ref().filter(|s| s.validate().is_ok()) {
+        if let Some(threshold) = split.duplicate_threshold.filter(|_| problems.is_empty() && !dry_run) {
+            let paths: Vec<PathBuf> = plan.images.iter().map(|i| i.path.clone()).collect();
+            let execution = options.execution.clone();
+            let hashes = tauri::async_runtime::spawn_blocking(move || phash::dhash_all(&paths, &execution))
+                .await
+                .map_err(|e| AugmentError::Internal(e.to_string()))??;
+            plan = plan.with_duplicate_groups(phash::group_near_duplicates(&hashes, threshold));
+        }
+        plan = plan.with_split(split, seed).map_err(|err| fail(&mut problems, err))?;
+    }
+    let inputs = plan.images.len();
+    let plan = match (&options.balance, options.target_total) {
+        (Some(balance), _) if balance.validate().is_ok() => plan.with_balance(balance, seed),
+        (None, Some(target)) if target >= inputs => plan.with_target_total(target, seed),
+        _ => Ok(plan.with_variants(options.variants_per_image.max(1))),
+    }
+    .map_err(|err| fail(&mut problems, err))?;
+
+    recipe.numbered = plan.numbered();
+    let prepared = PreparedRun { input_dir, output_dir, plan, recipe };
+    if !problems.is_empty() {
+        problems.extend(output_collisions(&prepared));
+        return Err(AugmentError::Validation(problems));
+    }
+    Ok(prepared)
+}
 
-    let label = window.label().to_string();
-    let _ = app.emit_to(&label, "augment-started", total);
+async fn start_run(app: AppHandle, window: WebviewWindow, jobs: &Jobs, request: RunRequest) -> Result<JobId, AugmentError> {
+    let RunRequest { directories, transformations, seed, options, expected } = request;
+    let record = RunRecord::new(directories.input.trim(), &transformations, seed, &options);
+    let prepared = prepare_run(&directories, transformations, seed, &options, false).await?;
 
-    // KLONY:
-    let app_for_blocking = app.clone();
-    let label_for_blocking = label.clone();
+    let collisions = output_collisions(&prepared);
+    if !collisions.is_empty() {
+        return Err(AugmentError::Validation(collisions));
+    }
 
-    let app_for_emit = app.clone();
-    let label_for_emit = label.clone();
+    let PreparedRun { input_dir, output_dir, plan, recipe } = prepared;
+    let total = plan.images.len();
 
-    tauri::async_runtime::spawn(async move {
-        let res = tauri::async_runtime::spawn_blocking(move || {
-            let (always, one_time) = split_transformations(&transformations);
-            let factory = Arc::new(TransformationFactory::new());
+    let summary = plan.summary();
+    if options.balance.is_some() {
+        write_report(&output_dir, "class_report.json", &summary)?;
+    }
+    if options.split.is_some() {
+        let manifest = plan.split_manifest(&input_dir);
+        write_report(&output_dir, "split_manifest.json", &manifest)?;
+    }
+    if options.split.as_ref().is_some_and(|s| s.duplicate_threshold.is_some()) {
+        let groups = plan.duplicate_groups(&input_dir);
+        write_report(&output_dir, "duplicate_groups.json", &groups)?;
+    }
 
-            augment_all(
-                &image_paths,
+    let (job_id, control) = jobs.start();
+
+    let events = WindowEvents { app, label: window.label().to_string() };
+    events.emit("augment-planned", summary.clone());
+    events.emit("augment-started", total);
+
+    // klon używany wewnątrz wątku
+    let events_for_blocking = events.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let res = tauri::async_runtime::spawn_blocking(move || -> Result<Outcome, AugmentError> {
+            let outcome = augment_all(
+                &plan,
                 &input_dir,
                 &output_dir,
-                factory.clone(),
-                &always,
-                &one_time,
-                seed,
-                &app_for_blocking,   // <- używamy klona wewnątrz wątku
-                &label_for_blocking, // <- i jego labela
-                total,
-            )
-            .map_err(|e| e.to_string()) // zamiana błędu na String, żeby był Send
+                &recipe,
+                &options.execution,
+                control,
+                &events_for_blocking,
+            )?;
+
+            if let Outcome::Finished { run, entries } = &outcome {
+                let memory = options.execution.memory_budget();
+                if let Some(report) = &options.report {
+                    let stats = ReportStats { plan: &summary, run, seed: recipe.seed };
+                    report::write(&output_dir, entries.clone(), &stats, report, &memory)?;
+                }
+
+                let record = record.complete(&output_dir, entries)?;
+                if let Some(expected) = &expected {
+                    let mut report = ReproductionReport::compare(&expected.record, &record);
+                    // różnice sprawdzamy, odtwarzając zapisane parametry zamiast losować je od nowa
+                    if !report.mismatched.is_empty() {
+                        // zapisany run tylko czytamy, może leżeć w katalogu tylko do odczytu
+                        let recorded = provenance::read_entries(&expected.output_dir)
+                            .map_err(|source| AugmentError::Read { path: expected.output_dir.clone(), source })?;
+                        let factory = TransformationFactory::new();
+                        report.check_replays(|output| {
+                            let entry = recorded.iter().find(|e| e.output == output)?;
+                            replay_output(entry, &input_dir, &factory, &memory)
+                        });
+                    }
+                    report.write(&output_dir)?;
+                    events_for_blocking.emit("augment-reproduced", report);
+                }
+            }
+            Ok(outcome)
         })
         .await;
 
+        events.app.state::<Jobs>().finish(job_id);
+
         match res {
-            Ok(Ok(())) => {
-                let _ = app_for_emit.emit_to(&label_for_emit, "augment-finished", ());
-            }
-            Ok(Err(err_str)) => {
-                let _ = app_for_emit.emit_to(&label_for_emit, "augment-error", err_str);
-            }
+            Ok(Ok(Outcome::Finished { run, .. })) => events.emit("augment-finished", run),
+            Ok(Ok(Outcome::Cancelled { processed })) => events.emit("augment-cancelled", processed),
+            Ok(Err(err)) => events.emit("augment-error", &err),
             Err(join_err) => {
-                let _ = app_for_emit.emit_to(&label_for_emit, "augment-error", join_err.to_string());
+                events.emit("augment-error", &AugmentError::Internal(join_err.to_string()))
             }
         }
     });
 
-    Ok("Dataset augmentation started.".into())
+    Ok(job_id)
+}
+
+/// Every registered transformation, in registration order.
+#[tauri::command]
+fn list_transformations() -> Vec<TransformationDescriptor> {
+    TransformationFactory::shared().describe_all()
+}
+
+/// Thumbnails of what the current selection does to `sample`, or to the first input image.
+#[tauri::command]
+async fn preview_transformations(
+    input: String,
+    sample: Option<String>,
+    transformations: Vec<String>,
+    seed: u64,
+    options: Option<AugmentOptions>,
+    max_size: Option<u32>,
+) -> Result<Vec<Preview>, AugmentError> {
+    let options = options.unwrap_or_default();
+    options.execution.validate()?;
+    let memory = options.execution.memory_budget();
+    let recipe = Recipe {
+        transformations,
+        rules: options.rules,
+        seed,
+        seeding: options.seeding,
+        numbered: false,
+        on_conflict: options.on_conflict,
+    };
+
+    let request = preview::begin_request();
+    tauri::async_runtime::spawn_blocking(move || {
+        let input_dir = Path::new(input.trim());
+        let sample = match sample {
+            Some(sample) => PathBuf::from(sample),
+            None => collect_image_paths(input_dir, &[])?
+                .into_iter()
+                .next()
+                .ok_or_else(|| AugmentError::InputEmpty(input_dir.to_path_buf()))?,
+        };
+        // seed i reguły zależą od ścieżki względnej, tak jak w runie
+        let relative_path = sample.strip_prefix(input_dir).unwrap_or(Path::new(sample.file_name().unwrap_or_default()));
+        preview::render(&sample, relative_path, &recipe, &memory, max_size.unwrap_or(DEFAULT_PREVIEW_SIZE), request)
+    })
+    .await
+    .map_err(|e| AugmentError::Internal(e.to_string()))?
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageProvenance {
+    // względem wybranego katalogu
+    path: String,
+    provenance: Option<EmbeddedProvenance>,
+    // uszkodzony plik nie przerywa przeglądania reszty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<FileError>,
+}
+
+/// Reads provenance embedded in every PNG under `directory`. Files that can't be read get an error each.
+#[tauri::command]
+async fn read_image_provenance(directory: String) -> Result<Vec<ImageProvenance>, AugmentError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = Path::new(directory.trim());
+        let images = collect_image_paths(dir, &[])?
+            .into_iter()
+            .map(|path| {
+                let (provenance, error) = match metadata::read(&path) {
+                    Ok(provenance) => (provenance, None),
+                    Err(err) => (None, Some(err.into())),
+                };
+                let path = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().into_owned();
+                ImageProvenance { path, provenance, error }
+            })
+            .collect();
+        Ok(images)
+    })
+    .await
+    .map_err(|e| AugmentError::Internal(e.to_string()))?
+}
+
+#[tauri::command]
+fn cancel_augmentation(jobs: State<'_, Jobs>, job_id: JobId) -> Result<(), AugmentError> {
+    jobs.get(job_id)?.cancel();
+    Ok(())
+}
+
+#[tauri::command]
+fn pause_augmentation(jobs: State<'_, Jobs>, job_id: JobId) -> Result<(), AugmentError> {
+    jobs.get(job_id)?.pause();
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_augmentation(jobs: State<'_, Jobs>, job_id: JobId) -> Result<(), AugmentError> {
+    jobs.get(job_id)?.resume();
+    Ok(())
 }
 
 fn augment_all(
-    image_paths: &[PathBuf],
+    plan: &Plan,
     input_dir: &Path,
     output_dir: &Path,
-    factory: Arc<TransformationFactory>,
-    always_transformations: &[&str],
-    one_time_transformations: &[&str],
-    base_seed: u64,
-    app: &AppHandle,
-    label: &str,
-    total: usize,
-) -> Result<(), AnyErr> {
-    fs::create_dir_all(output_dir)?;
+    recipe: &Recipe,
+    execution: &ExecutionOptions,
+    control: Arc<JobControl>,
+    events: &WindowEvents,
+) -> Result<Outcome, AugmentError> {
+    let write_error = |source| AugmentError::Write { path: output_dir.to_path_buf(), source };
+    fs::create_dir_all(output_dir).map_err(write_error)?;
+    let total = plan.images.len();
+    let journal = Journal::open(output_dir)
+        .map_err(|source| AugmentError::Journal { path: output_dir.to_path_buf(), source })?;
+    let context = WorkerContext {
+        factory: TransformationFactory::new(),
+        journal,
+        manifest: Manifest::open(output_dir).map_err(write_error)?,
+        io: execution.io_limits(),
+        memory: execution.memory_budget(),
+    };
+    let failures = FailureLog::new(output_dir).map_err(write_error)?;
 
     // współdzielone
     let processed = Arc::new(AtomicUsize::new(0));
-    let app = Arc::new(app.clone());
-    let label = label.to_string();
+    let skipped = AtomicUsize::new(0);
+    // obrazy z co najmniej jednym błędem; jeden obraz może zgłosić ich kilka
+    let failed = AtomicUsize::new(0);
 
     // Flaga do zamknięcia reportera
     let running = Arc::new(AtomicBool::new(true));
 
     // --- Reporter: JEDNA nitka emituje progres co ~100ms ---
     let processed_for_reporter = processed.clone();
-    let app_for_reporter = app.clone();
-    let label_for_reporter = label.clone();
+    let events_for_reporter = events.clone();
     let running_for_reporter = running.clone();
+    let control_for_reporter = control.clone();
 
     let reporter = std::thread::spawn(move || {
-        let mut last = 0usize;
+        let mut last = (0usize, false);
         while running_for_reporter.load(Ordering::Relaxed) {
             let done = processed_for_reporter.load(Ordering::Relaxed);
-            if done != last {
+            let paused = control_for_reporter.is_paused();
+            if (done, paused) != last {
                 let percent = (((done as f64 / total as f64) * 100.0).round() as u8).min(100);
-                let _ = app_for_reporter.emit_to(
-                    &label_for_reporter,
+                events_for_reporter.emit(
                     "augment-progress",
-                    AugmentProgress { processed: done, total, percent },
+                    AugmentProgress { processed: done, total, percent, paused },
                 );
-                last = done;
+                last = (done, paused);
             }
             std::thread::sleep(Duration::from_millis(100));
         }
         // finalny „dopędzacz” po zamknięciu
         let done = processed_for_reporter.load(Ordering::Relaxed);
         let percent = (((done as f64 / total as f64) * 100.0).round() as u8).min(100);
-        let _ = app_for_reporter.emit_to(
-            &label_for_reporter,
+        events_for_reporter.emit(
             "augment-progress",
-            AugmentProgress { processed: done, total, percent },
+            AugmentProgress { processed: done, total, percent, paused: false },
         );
     });
 
     // --- Równoległa praca ---
-    // zostaw 1 rdzeń dla UI/systemu
-    let num_threads = num_cpus::get().saturating_sub(1).max(1);
-    let pool = ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build()
-        .map_err(|e| format!("Failed to build rayon pool: {e}"))?;
-    let chunk = 8;
+    let pool = execution.thread_pool()?;
+
+    // błędy są rzadkie, więc te emitujemy od razu z wątków roboczych
+    let report = |failure: FileError| {
+        failures.record(&failure);
+        events.emit("augment-file-error", &failure);
+        if execution.fail_fast {
+            control.cancel();
+        }
+    };
 
     pool.install(|| {
-        image_paths.par_chunks(chunk).for_each(|paths| {
-            for path in paths {
-                if let Err(err) = process_single(
-                    path,
-                    input_dir,
-                    output_dir,
-                    &factory,
-                    always_transformations,
-                    one_time_transformations,
-                    base_seed,
-                ) {
-                    eprintln!("Failed to process {}: {err}", path.display());
+        let chunks = execution.chunks(&plan.images, &context.io);
+        chunks.par_iter().for_each(|images| {
+            for image in images.iter() {
+                // pauzę i anulowanie sprawdzamy tylko między obrazami
+                control.wait_while_paused();
+                if control.is_cancelled() {
+                    return;
+                }
+                let image_failed = AtomicBool::new(false);
+                let report = |failure: FileError| {
+                    image_failed.store(true, Ordering::Relaxed);
+                    report(failure);
+                };
+                match process_single(image, input_dir, output_dir, recipe, &context, &report) {
+                    Ok(Processed::Written) => {}
+                    Ok(Processed::Skipped) => {
+                        skipped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(err) => report(err.into()),
+                }
+                if image_failed.load(Ordering::Relaxed) {
+                    failed.fetch_add(1, Ordering::Relaxed);
                 }
+                // tylko licznik, progres emituje reporter
+                processed.fetch_add(1, Ordering::Relaxed);
             }
-            // tylko licznik, ZERO emitów stąd
-            processed.fetch_add(paths.len(), Ordering::Relaxed);
         });
     });
 
@@ -191,85 +704,326 @@ fn augment_all(
     running.store(false, Ordering::Relaxed);
     let _ = reporter.join();
 
-    Ok(())
+    // manifest zapisujemy też po anulowaniu, żeby opisywał wszystko, co już powstało
+    let WorkerContext { manifest, .. } = context;
+    manifest.write().map_err(write_error)?;
+
+    if execution.fail_fast {
+        if let Some(failure) = failures.first() {
+            return Err(AugmentError::Stopped(Box::new(failure)));
+        }
+    }
+    if control.is_cancelled() {
+        return Ok(Outcome::Cancelled { processed: processed.load(Ordering::Relaxed) });
+    }
+    let run = RunSummary {
+        processed: processed.load(Ordering::Relaxed),
+        skipped: skipped.load(Ordering::Relaxed),
+        failed: failed.load(Ordering::Relaxed),
+    };
+    Ok(Outcome::Finished { run, entries: manifest.into_run_entries() })
 }
 
 
+// Współdzielone przez wątki robocze jednego runu
+struct WorkerContext {
+    factory: TransformationFactory,
+    journal: Journal,
+    manifest: Manifest,
+    io: IoLimits,
+    memory: MemoryBudget,
+}
+
 fn process_single(
-    path: &Path,
+    image: &PlannedImage,
     input_dir: &Path,
     output_dir: &Path,
-    factory: &TransformationFactory,
-    always_transformations: &[&str],
-    one_time_transformations: &[&str],
-    base_seed: u64,
-) -> Result<(), AnyErr> {
-    let relative_path = path.strip_prefix(input_dir)?;
-    let output_base = output_dir.join(relative_path).with_extension("");
+    recipe: &Recipe,
+    context: &WorkerContext,
+    report: &dyn Fn(FileError),
+) -> Result<Processed, AugmentError> {
+    let path = &image.path;
+    let relative_path = path
+        .strip_prefix(input_dir)
+        .map_err(|e| AugmentError::Internal(e.to_string()))?;
+    let (always, one_time) = recipe.resolve(relative_path, &image.class);
 
-    if let Some(parent) = output_base.parent() {
-        fs::create_dir_all(parent)?;
+    let bytes = {
+        let _read = context.io.reads.acquire();
+        fs::read(path).map_err(|source| AugmentError::Read { path: path.clone(), source })?
+    };
+    let input = relative_path.to_string_lossy().into_owned();
+    let input_hash = blake3::hash(&bytes).to_hex().to_string();
+    let recipe_hash = recipe_hash(recipe, image, &always, &one_time);
+    if context.journal.is_up_to_date(&input, &input_hash, &recipe_hash) {
+        context.manifest.keep(context.journal.finished_outputs(&input));
+        return Ok(Processed::Skipped);
     }
 
-    let img = image::open(path)?;
-    save_image(&output_base.with_extension("png"), &img)?;
+    let output_base = output_base(image, relative_path, output_dir);
 
-    let stem = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_lowercase())
-        .unwrap_or_default();
+    let mut outputs = Vec::new();
+    let mut provenance = Vec::new();
+    let mut save = |out: PathBuf, img: &DynamicImage, transforms: Vec<AppliedTransform>, variant: Option<u32>| {
+        let out = match recipe.on_conflict {
+            _ if !out.exists() => out,
+            ConflictPolicy::Overwrite => out,
+            ConflictPolicy::Skip => {
+                let kept = out.strip_prefix(output_dir).unwrap_or(&out).to_string_lossy().into_owned();
+                context.manifest.keep([&kept]);
+                outputs.push(kept);
+                return Ok(());
+            }
+            ConflictPolicy::Rename => free_path(&out),
+            ConflictPolicy::Fail => return Err(AugmentError::OutputExists(out)),
+        };
+        let embedded = EmbeddedProvenance {
+            source: input.clone(),
+            transforms,
+            base_seed: recipe.seed,
+            seeding: recipe.seeding,
+            app_version: APP_VERSION.to_string(),
+        };
+        // wpis przed zapisem, żeby po crashu w połowie obrazu wyniki nie zostały wzięte za wejścia
+        let relative_out = out.strip_prefix(output_dir).unwrap_or(&out).to_string_lossy().into_owned();
+        context
+            .journal
+            .record_pending(&input, &relative_out)
+            .map_err(|source| AugmentError::Journal { path: out.clone(), source })?;
+        let save_error = |source| AugmentError::Save { path: out.clone(), source };
+        // kodowanie jest ciężkie dla CPU, więc robimy je poza limitem równoczesnych zapisów
+        let bytes = ImageFormat::from_path(&out)
+            .and_then(|format| encode_image(img, format, &embedded))
+            .map_err(save_error)?;
+        {
+            let _write = context.io.writes.acquire();
+            write_atomically(&out, &bytes).map_err(|e| save_error(e.into()))?;
+        }
+        let output_hash = blake3::hash(&bytes).to_hex().to_string();
+        provenance.push((out.clone(), ProvenanceEntry {
+            source: embedded.source,
+            output: relative_out.clone(),
+            variant,
+            transforms: embedded.transforms,
+            base_seed: recipe.seed,
+            seeding: recipe.seeding,
+            source_hash: input_hash.clone(),
+            output_hash,
+            app_version: embedded.app_version,
+        }));
+        outputs.push(relative_out);
+        Ok(())
+    };
 
-    if one_time_transformations.is_empty() {
-        let mut result = img.clone();
+    let (mut img, _memory) = context.memory.decode(path, &bytes)?;
+    drop(bytes);
 
-        for t_name in always_transformations {
-            let t_seed = derive_seed_for_transform(&stem, base_seed, t_name);
-            result = apply_transformation(&result, t_name, t_seed, factory).unwrap_or(result);
+    // obraz z nieudaną transformacją nie trafia do dziennika, żeby ponowić go przy następnym runie
+    let mut complete = true;
+    // zapisane wyjścia trafiają do manifestu także wtedy, gdy obraz przerwał błąd
+    let written = (|| -> Result<(), AugmentError> {
+        // przy zapisie w miejscu nie nadpisujemy oryginału jego własną kopią, także gdy `Cat.PNG` -> `Cat.png`
+        let original = png_path(&output_base);
+        if path_key(&original) != path_key(path) {
+            save(original, &img, Vec::new(), None)?;
         }
 
-        let shifted_path = output_base.with_file_name(format!(
-            "{}_shifted",
-            output_base
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("output")
-        ));
-        save_image(&shifted_path.with_extension("png"), &result)?;
-        return Ok(());
+        let mut transform = |img: &DynamicImage, transformation: &str, variant: u32| {
+            let t_seed = derive_seed(recipe.seeding, relative_path, recipe.seed, transformation, variant);
+            apply_transformation(img, transformation, t_seed, &context.factory)
+                .map(|(img, params)| (img, AppliedTransform { name: transformation.to_string(), seed: t_seed, params }))
+                .map_err(|message| {
+                    complete = false;
+                    report(
+                        AugmentError::Transform {
+                            path: path.clone(),
+                            transform: transformation.to_string(),
+                            message,
+                        }
+                        .into(),
+                    );
+                })
+                .ok()
+        };
+
+        let variants = image.variants();
+        for variant in 0..variants {
+            // ostatni wariant może być niepełny (tryb docelowej liczby obrazów)
+            let remaining = image.outputs - variant * image.outputs_per_variant;
+            let mut base = match variant + 1 < variants {
+                true => img.clone(),
+                // ostatni wariant przejmuje oryginał, zamiast trzymać w pamięci jego kopię
+                false => std::mem::replace(&mut img, DynamicImage::new_rgba8(0, 0)),
+            };
+            let variant = variant as u32;
+
+            let mut chain = Vec::new();
+            for transformation in &always {
+                if let Some((shifted, applied)) = transform(&base, transformation, variant) {
+                    base = shifted;
+                    chain.push(applied);
+                }
+            }
+
+            if one_time.is_empty() {
+                let out = variant_output_path(&output_base, "shifted", variant, recipe.numbered);
+                save(out, &base, chain, Some(variant))?;
+                continue;
+            }
+
+            for transformation in one_time.iter().take(remaining) {
+                if let Some((transformed, applied)) = transform(&base, transformation, variant) {
+                    let out = variant_output_path(&output_base, transformation, variant, recipe.numbered);
+                    let mut transforms = chain.clone();
+                    transforms.push(applied);
+                    save(out, &transformed, transforms, Some(variant))?;
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    // manifest przed dziennikiem: obraz uznany za gotowy zawsze ma swoje wpisy
+    context
+        .manifest
+        .record(provenance)
+        .map_err(|source| AugmentError::Write { path: output_dir.to_path_buf(), source })?;
+    written?;
+    if complete {
+        context
+            .journal
+            .record(&JournalEntry { input, input_hash, recipe_hash, outputs })
+            .map_err(|source| AugmentError::Journal { path: path.clone(), source })?;
+    }
+    Ok(Processed::Written)
+}
+
+fn output_base(image: &PlannedImage, relative_path: &Path, output_dir: &Path) -> PathBuf {
+    match image.split {
+        Some(split) => output_dir.join(split.dir_name()),
+        None => output_dir.to_path_buf(),
+    }
+    .join(relative_path)
+    .with_extension("")
+}
+
+/// Every file `process_single` writes for the image, in the same order.
+fn planned_outputs(image: &PlannedImage, relative_path: &Path, output_dir: &Path, recipe: &Recipe) -> Vec<PathBuf> {
+    let (_, one_time) = recipe.resolve(relative_path, &image.class);
+    let output_base = output_base(image, relative_path, output_dir);
+
+    let mut outputs = vec![png_path(&output_base)];
+    for variant in 0..image.variants() {
+        let remaining = image.outputs - variant * image.outputs_per_variant;
+        let variant = variant as u32;
+
+        if one_time.is_empty() {
+            outputs.push(variant_output_path(&output_base, "shifted", variant, recipe.numbered));
+        }
+        for transformation in one_time.iter().take(remaining) {
+            outputs.push(variant_output_path(&output_base, transformation, variant, recipe.numbered));
+        }
     }
+    outputs
+}
 
-    let mut base = img.clone();
-    for transformation in always_transformations {
-        let t_seed = derive_seed_for_transform(&stem, base_seed, transformation);
-        base = apply_transformation(&base, transformation, t_seed, factory).unwrap_or(base);
-    }
-
-    for transformation in one_time_transformations {
-        let t_seed = derive_seed_for_transform(&stem, base_seed, transformation);
-        if let Some(transformed) = apply_transformation(&base, transformation, t_seed, factory) {
-            let out = output_base.with_file_name(format!(
-                "{}_{}",
-                output_base
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("output"),
-                transformation
-            ));
-            save_image(&out.with_extension("png"), &transformed)?;
+// Dwa wejścia piszące w to samo miejsce, np. `a.png` i `a.PNG`. Bez rozróżniania wielkości liter,
+// bo na Windowsie i macOS to ten sam plik.
+fn output_collisions(prepared: &PreparedRun) -> Vec<AugmentError> {
+    let mut owners: HashMap<OsString, &Path> = HashMap::new();
+    let mut collisions: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+
+    for (image, outputs) in prepared.outputs() {
+        for output in outputs {
+            let key = path_key(&output);
+            match owners.get(&key) {
+                Some(&owner) if owner != image.path => {
+                    collisions
+                        .entry(output)
+                        .or_default()
+                        .extend([owner.to_path_buf(), image.path.clone()]);
+                }
+                Some(_) => {}
+                None => {
+                    owners.insert(key, &image.path);
+                }
+            }
         }
     }
 
-    Ok(())
+    collisions
+        .into_iter()
+        .map(|(output, inputs)| AugmentError::OutputCollision { output, inputs: inputs.into_iter().collect() })
+        .collect()
+}
+
+// Klucz porównania ścieżek niezależny od wielkości liter;
+// nazwy nie-UTF-8 porównujemy dokładnie, bez stratnej konwersji
+fn path_key(path: &Path) -> OsString {
+    match path.to_str() {
+        Some(name) => name.to_lowercase().into(),
+        None => path.as_os_str().to_os_string(),
+    }
+}
+
+// `cat_mirror.png` -> pierwsza wolna z `cat_mirror-2.png`, `cat_mirror-3.png`, ...
+fn free_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default();
+    let extension = path.extension().unwrap_or_default();
+    (2..)
+        .map(|n| {
+            let mut file_name = stem.to_os_string();
+            file_name.push(format!("-{n}."));
+            file_name.push(extension);
+            path.with_file_name(file_name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range")
+}
+
+// Wszystko, od czego zależą wyjścia danego obrazu
+fn recipe_hash(recipe: &Recipe, image: &PlannedImage, always: &[&str], one_time: &[&str]) -> String {
+    let mut h = Hasher::new();
+    h.update(&recipe.seed.to_le_bytes());
+    h.update(&[recipe.seeding as u8]);
+    for (kind, list) in [(b'a', always), (b'o', one_time)] {
+        for transformation in list {
+            h.update(&[kind]);
+            h.update(transformation.as_bytes());
+            h.update(&[0]);
+        }
+    }
+    h.update(&(image.outputs as u64).to_le_bytes());
+    h.update(&[recipe.numbered as u8]);
+    h.update(image.split.map_or("", |s| s.dir_name()).as_bytes());
+    h.finalize().to_hex().to_string()
+}
+
+/// `cat` + `mirror` -> `cat_mirror.png`, or `cat_mirror_3.png` when more than one variant is generated.
+fn variant_output_path(output_base: &Path, suffix: &str, variant: u32, numbered: bool) -> PathBuf {
+    let mut file_name = output_base.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!("_{suffix}"));
+    if numbered {
+        file_name.push(format!("_{}", variant + 1));
+    }
+    file_name.push(".png");
+    output_base.with_file_name(file_name)
 }
 
-fn split_transformations<'a>(list: &'a [String]) -> (Vec<&'a str>, Vec<&'a str>) {
+// Dopisuje `.png` do nazwy; with_extension obciąłby nazwę z kropką, np. `cat.v2`
+fn png_path(output_base: &Path) -> PathBuf {
+    let mut file_name = output_base.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".png");
+    output_base.with_file_name(file_name)
+}
+
+fn split_transformations<'a>(list: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let factory = TransformationFactory::shared();
     let mut always = Vec::new();
     let mut one_time = Vec::new();
 
-    for item in list {
-        let transformation = item.as_str();
-        if matches!(transformation, "hor_shift" | "ver_shift") {
+    for &transformation in list {
+        if factory.is_always(transformation) {
             always.push(transformation);
         } else {
             one_time.push(transformation);
@@ -278,11 +1032,71 @@ fn split_transformations<'a>(list: &'a [String]) -> (Vec<&'a str>, Vec<&'a str>)
     (always, one_time)
 }
 
-fn save_image(path: &Path, img: &DynamicImage) -> image::ImageResult<()> {
+fn write_report<T: Serialize>(output_dir: &Path, file_name: &str, report: &T) -> Result<(), AugmentError> {
+    let path = output_dir.join(file_name);
+    let write = || -> std::io::Result<()> {
+        fs::create_dir_all(output_dir)?;
+        let file = fs::File::create(&path)?;
+        serde_json::to_writer_pretty(file, report)?;
+        Ok(())
+    };
+    write().map_err(|source| AugmentError::Write { path: path.clone(), source })
+}
+
+// Zapis przez plik tymczasowy + rename, żeby nigdy nie zostawić uciętego obrazu
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).ok();
     }
-    img.save(path)
+    let mut temp_name = OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    if let Err(err) = fs::write(&temp_path, bytes) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+    fs::rename(&temp_path, path)
+}
+
+// Kodujemy w pamięci, żeby hash liczyć bez ponownego czytania pliku; PNG dostaje osadzone provenance
+fn encode_image(img: &DynamicImage, format: ImageFormat, provenance: &EmbeddedProvenance) -> image::ImageResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut bytes), format)?;
+    if format == ImageFormat::Png {
+        bytes = metadata::embed(&bytes, provenance).map_err(|e| {
+            ImageError::Encoding(image::error::EncodingError::new(ImageFormat::Png.into(), e))
+        })?;
+    }
+    Ok(bytes)
+}
+
+/// Hash of the file `entry` describes, rebuilt from its source with the recorded parameters.
+/// `None` when the source or a transformation is missing or fails.
+fn replay_output(
+    entry: &ProvenanceEntry,
+    input_dir: &Path,
+    factory: &TransformationFactory,
+    memory: &MemoryBudget,
+) -> Option<String> {
+    let source = input_dir.join(&entry.source);
+    let bytes = fs::read(&source).ok()?;
+    let (mut img, _memory) = memory.decode(&source, &bytes).ok()?;
+    for transform in &entry.transforms {
+        img = factory.replay(&transform.name, &img, &transform.params)?.ok()?;
+    }
+
+    // metadane jak w zapisanym pliku, łącznie z wersją aplikacji
+    let embedded = EmbeddedProvenance {
+        source: entry.source.clone(),
+        transforms: entry.transforms.clone(),
+        base_seed: entry.base_seed,
+        seeding: entry.seeding,
+        app_version: entry.app_version.clone(),
+    };
+    let bytes = encode_image(&img, ImageFormat::Png, &embedded).ok()?;
+    Some(blake3::hash(&bytes).to_hex().to_string())
 }
 
 fn apply_transformation(
@@ -290,55 +1104,38 @@ fn apply_transformation(
     transformation_name: &str,
     seed: u64,
     factory: &TransformationFactory,
-) -> Option<DynamicImage> {
+) -> Result<(DynamicImage, Params), String> {
     let mut rng = StdRng::seed_from_u64(seed);
 
-    match factory.create(transformation_name) {
-        Some(transformation) => match transformation.apply(img, &mut rng) {
-            Ok(transformed_img) => Some(transformed_img),
-            Err(_) => {
-                eprintln!(
-                    "Error applying transformation '{transformation_name}', skipping.",
-                );
-                None
-            }
-        },
-        None => {
-            eprintln!(
-                "Warning: Transformation '{transformation_name}' not implemented, skipping.",
-            );
-            None
-        }
-    }
+    let transformation = factory
+        .create(transformation_name)
+        .ok_or_else(|| format!("Transformation '{transformation_name}' not implemented."))?;
+    transformation.apply(img, &mut rng).map_err(|e| e.to_string())
 }
 
-fn derive_seed_for_transform(stem: &str, base_seed: u64, transform: &str) -> u64 {
-    let mut h = Hasher::new();
-    h.update(&base_seed.to_le_bytes());
-    h.update(stem.as_bytes());
-    h.update(transform.as_bytes());
-
-    let out = h.finalize();
-    let mut eight = [0u8; 8];
-
-    eight.copy_from_slice(&out.as_bytes()[..8]);
-    u64::from_le_bytes(eight)
-}
-
-fn collect_image_paths(input_dir: &Path) -> io::Result<Vec<PathBuf>> {
+/// Collects PNG images under `input_dir`, skipping `excluded` directories and files.
+fn collect_image_paths(input_dir: &Path, excluded: &[PathBuf]) -> Result<Vec<PathBuf>, AugmentError> {
+    if !input_dir.is_dir() {
+        return Err(AugmentError::InputNotFound(input_dir.to_path_buf()));
+    }
     let mut out = Vec::new();
-    collect_recursive(input_dir, &mut out)?;
+    collect_recursive(input_dir, excluded, &mut out)?;
     out.sort();
     Ok(out)
 }
 
-fn collect_recursive(dir: &Path, acc: &mut Vec<PathBuf>) -> io::Result<()> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
+fn collect_recursive(dir: &Path, excluded: &[PathBuf], acc: &mut Vec<PathBuf>) -> Result<(), AugmentError> {
+    let read_error = |source| AugmentError::Read { path: dir.to_path_buf(), source };
+
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
         let path = entry.path();
+        if excluded.contains(&path) {
+            continue;
+        }
 
         if path.is_dir() {
-            collect_recursive(&path, acc)?;
+            collect_recursive(&path, excluded, acc)?;
 
         } else if path.is_file() {
             if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -351,7 +1148,7 @@ fn collect_recursive(dir: &Path, acc: &mut Vec<PathBuf>) -> io::Result<()> {
     Ok(())
 }
 
-fn check_missing_directories(directories: &Directories) -> Result<(), String> {
+fn check_missing_directories(directories: &Directories) -> Result<(), AugmentError> {
     let mut missing_directories = Vec::new();
 
     if directories.input.trim().is_empty() {
@@ -362,21 +1159,228 @@ fn check_missing_directories(directories: &Directories) -> Result<(), String> {
     }
 
     if !missing_directories.is_empty() {
-        return Err(format!(
-            "Directories {} aren't set.", 
-            missing_directories.join(", "))
-        );
+        return Err(AugmentError::MissingDirectories(missing_directories));
     }
 
     Ok(())
 }
 
+/// Checks everything that can be checked before planning. Returns the input images found,
+/// possibly none, with all problems; `prepare_run` adds output collisions before reporting them.
+fn validate_run(
+    directories: &Directories,
+    transformations: &[String],
+    options: &AugmentOptions,
+    dry_run: bool,
+) -> (Vec<PathBuf>, Vec<AugmentError>) {
+    let mut problems = Vec::new();
+    if let Err(err) = check_missing_directories(directories) {
+        problems.push(err);
+    }
+
+    let factory = TransformationFactory::new();
+    let unknown: BTreeSet<&str> = transformations
+        .iter()
+        .map(String::as_str)
+        .chain(options.rules.iter().flat_map(|r| r.transformations()))
+        .filter(|name| !factory.contains(name))
+        .collect();
+    problems.extend(unknown.into_iter().map(|name| AugmentError::UnknownTransformation(name.into())));
+    let option_checks = [
+        options.execution.validate(),
+        options.split.as_ref().map_or(Ok(()), SplitOptions::validate),
+        options.balance.as_ref().map_or(Ok(()), BalanceOptions::validate),
+    ];
+    problems.extend(option_checks.into_iter().filter_map(Result::err));
+
+    let input = directories.input.trim();
+    let output = directories.output.trim();
+
+    if !output.is_empty() {
+        if let Err(source) = check_writable(Path::new(output), !dry_run) {
+            problems.push(AugmentError::OutputNotWritable { path: output.into(), source });
+        }
+    }
+
+    // Wyjście wewnątrz wejścia: bez potwierdzenia odmawiamy, z potwierdzeniem nie skanujemy wyników
+    let mut excluded = Vec::new();
+    if !input.is_empty() && !output.is_empty() {
+        let (input_dir, output_dir) = (Path::new(input), Path::new(output));
+        if let Ok(nested) = resolve_path(output_dir).strip_prefix(resolve_path(input_dir)) {
+            let in_place = nested.as_os_str().is_empty();
+            if !options.write_into_input {
+                problems.push(match in_place {
+                    true => AugmentError::OutputIsInput(input.into()),
+                    false => AugmentError::OutputInsideInput { input: input.into(), output: output.into() },
+                });
+            } else if in_place {
+                // wyniki poprzednich runów leżą obok oryginałów, znamy je tylko z dziennika,
+                // razem z tymi zapisanymi przez przerwane obrazy
+                match Journal::recorded_outputs(output_dir) {
+                    Ok(recorded) => excluded.extend(recorded.iter().map(|o| input_dir.join(o))),
+                    Err(source) => problems.push(AugmentError::Journal { path: output.into(), source }),
+                }
+                // arkusz raportu to PNG spoza dziennika, inaczej następny run wziąłby go za wejście
+                excluded.push(input_dir.join(report::CONTACT_SHEET_FILE));
+            } else {
+                excluded.push(input_dir.join(nested));
+            }
+        }
+    }
+
+    let mut image_paths = Vec::new();
+    if !input.is_empty() {
+        match collect_image_paths(Path::new(input), &excluded) {
+            Ok(paths) if paths.is_empty() => problems.push(AugmentError::InputEmpty(input.into())),
+            Ok(paths) => image_paths = paths,
+            Err(err) => problems.push(err),
+        }
+    }
+
+    // liczba obrazów jest znana dopiero po przeskanowaniu wejścia
+    if let (None, Some(target)) = (&options.balance, options.target_total) {
+        if !image_paths.is_empty() {
+            if let Err(err) = validate_target_total(target, image_paths.len()) {
+                problems.push(err);
+            }
+        }
+    }
+
+    (image_paths, problems)
+}
+
+// Próbny zapis w najbliższym istniejącym katalogu, bez tworzenia katalogu wyjściowego.
+// Bez `probe` sprawdzamy tylko uprawnienia, niczego nie zapisując.
+fn check_writable(dir: &Path, probe: bool) -> io::Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|p| p.is_dir())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no existing parent directory"))?;
+    if !probe {
+        if fs::metadata(existing)?.permissions().readonly() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "directory is read-only"));
+        }
+        return Ok(());
+    }
+
+    let probe = existing.join(format!(".augmentator-write-test-{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+// Ścieżka absolutna z rozwiązanymi symlinkami tam, gdzie już istnieje
+fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let existing = absolute.ancestors().find(|p| p.exists()).unwrap_or(&absolute);
+
+    match (fs::canonicalize(existing), absolute.strip_prefix(existing)) {
+        (Ok(canonical), Ok(rest)) => canonical.join(rest),
+        _ => absolute,
+    }
+}
+
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![augment_dataset])
+        .manage(Jobs::default())
+        .invoke_handler(tauri::generate_handler![
+            list_transformations,
+            augment_dataset,
+            plan_augmentation,
+            reproduce_augmentation,
+            read_image_provenance,
+            preview_transformations,
+            cancel_augmentation,
+            pause_augmentation,
+            resume_augmentation,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepared(inputs: &[&str], transformations: &[&str]) -> PreparedRun {
+        let (input_dir, output_dir) = (PathBuf::from("in"), PathBuf::from("out"));
+        let recipe = Recipe {
+            transformations: transformations.iter().map(|t| t.to_string()).collect(),
+            rules: Vec::new(),
+            seed: 0,
+            seeding: SeedScheme::default(),
+            numbered: false,
+            on_conflict: ConflictPolicy::default(),
+        };
+        let paths = inputs.iter().map(|p| input_dir.join(p)).collect();
+        let plan = Plan::new(paths, &input_dir, |relative_path, class| recipe.resolve(relative_path, class).1.len())
+            .with_variants(1);
+        PreparedRun { input_dir, output_dir, plan, recipe }
+    }
+
+    #[test]
+    fn variant_output_paths() {
+        let base = Path::new("out/cat");
+        assert_eq!(variant_output_path(base, "mirror", 0, false), Path::new("out/cat_mirror.png"));
+        assert_eq!(variant_output_path(base, "mirror", 0, true), Path::new("out/cat_mirror_1.png"));
+        assert_eq!(variant_output_path(base, "mirror", 2, true), Path::new("out/cat_mirror_3.png"));
+        // kropka w nazwie zostaje
+        assert_eq!(variant_output_path(Path::new("out/cat.v2"), "blur", 0, false), Path::new("out/cat.v2_blur.png"));
+    }
+
+    #[test]
+    fn path_key_ignores_case_only_for_utf8() {
+        assert_eq!(path_key(Path::new("Train/Cat.PNG")), path_key(Path::new("train/cat.png")));
+        assert_ne!(path_key(Path::new("cat.png")), path_key(Path::new("cat2.png")));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let upper = Path::new(std::ffi::OsStr::from_bytes(b"CAF\xc9.png"));
+            let lower = Path::new(std::ffi::OsStr::from_bytes(b"caf\xc9.png"));
+            assert_eq!(path_key(upper), upper.as_os_str());
+            assert_ne!(path_key(upper), path_key(lower));
+        }
+    }
+
+    #[test]
+    fn free_path_skips_taken_names() {
+        let dir = std::env::temp_dir().join(format!("augmentator-free-path-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for taken in ["cat.png", "cat-2.png", "cat.v2.png"] {
+            fs::write(dir.join(taken), b"").unwrap();
+        }
+
+        assert_eq!(free_path(&dir.join("cat.png")), dir.join("cat-3.png"));
+        assert_eq!(free_path(&dir.join("cat.v2.png")), dir.join("cat.v2-2.png"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn output_collisions_ignore_case() {
+        let run = prepared(&["a.png", "A.PNG", "b.png"], &["mirror"]);
+        let collisions = output_collisions(&run);
+
+        let outputs: Vec<&Path> = collisions
+            .iter()
+            .map(|c| match c {
+                AugmentError::OutputCollision { output, inputs } => {
+                    assert_eq!(inputs, &[PathBuf::from("in/A.PNG"), PathBuf::from("in/a.png")]);
+                    output.as_path()
+                }
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        // zgłaszane pod nazwą drugiego wejścia
+        assert_eq!(outputs, [Path::new("out/A.png"), Path::new("out/A_mirror.png")]);
+    }
+
+    #[test]
+    fn distinct_outputs_dont_collide() {
+        let run = prepared(&["cat/001.png", "dog/001.png", "001.png"], &["mirror", "blur"]);
+        assert!(output_collisions(&run).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use png::text_metadata::{EncodableTextChunk, ITXtChunk, TEXtChunk};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use crate::error::AugmentError;
+use crate::provenance::{AppliedTransform, APP_VERSION};
+use crate::seeding::SeedScheme;
+
+const PROVENANCE_KEYWORD: &str = "augmentator:provenance";
+
+/// Provenance stored inside each output PNG, readable without the manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddedProvenance {
+    // względem katalogu wejściowego
+    pub source: String,
+    pub transforms: Vec<AppliedTransform>,
+    pub base_seed: u64,
+    pub seeding: SeedScheme,
+    pub app_version: String,
+}
+
+/// Inserts the provenance as an `iTXt` chunk and the tool as a `Software` `tEXt` chunk
+/// right after `IHDR` of an encoded PNG.
+pub fn embed(png: &[u8], provenance: &EmbeddedProvenance) -> Result<Vec<u8>, png::EncodingError> {
+    let json = serde_json::to_string(provenance).map_err(std::io::Error::from)?;
+    let mut chunks = Vec::new();
+    ITXtChunk::new(PROVENANCE_KEYWORD, json).encode(&mut chunks)?;
+    TEXtChunk::new("Software", format!("augmentator {APP_VERSION}")).encode(&mut chunks)?;
+
+    // sygnatura (8) + IHDR: długość (4), typ (4), dane, CRC (4)
+    let ihdr_len = png
+        .get(8..12)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "not a PNG"))?;
+    let split = (8 + 12 + ihdr_len).min(png.len());
+
+    let mut out = Vec::with_capacity(png.len() + chunks.len());
+    out.extend_from_slice(&png[..split]);
+    out.extend_from_slice(&chunks);
+    out.extend_from_slice(&png[split..]);
+    Ok(out)
+}
+
+/// What the PNG header says about the decoded image, without decoding any pixels.
+pub struct PngHeader {
+    pub width: u32,
+    pub height: u32,
+    // po rozwinięciu palety i głębi < 8 bitów, tak jak dekoduje `image`
+    pub bytes_per_pixel: usize,
+}
+
+pub fn read_header<R: Read>(reader: R) -> Result<PngHeader, png::DecodingError> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let reader = decoder.read_info()?;
+    let (color_type, bit_depth) = reader.output_color_type();
+    let bytes_per_sample = if bit_depth == png::BitDepth::Sixteen { 2 } else { 1 };
+    Ok(PngHeader {
+        width: reader.info().width,
+        height: reader.info().height,
+        bytes_per_pixel: color_type.samples() * bytes_per_sample,
+    })
+}
+
+/// Reads embedded provenance back. `None` for PNGs written without it.
+pub fn read(path: &Path) -> Result<Option<EmbeddedProvenance>, AugmentError> {
+    let read_error = |source| AugmentError::Read { path: path.to_path_buf(), source };
+    let file = File::open(path).map_err(read_error)?;
+
+    // read_info czyta chunki aż do pierwszego IDAT, a nasze są tuż za IHDR
+    let reader = png::Decoder::new(BufReader::new(file)).read_info().map_err(|e| AugmentError::Decode {
+        path: path.to_path_buf(),
+        source: image::ImageError::Decoding(image::error::DecodingError::new(
+            image::ImageFormat::Png.into(),
+            e,
+        )),
+    })?;
+
+    let text = reader
+        .info()
+        .utf8_text
+        .iter()
+        .find(|chunk| chunk.keyword == PROVENANCE_KEYWORD)
+        .and_then(|chunk| chunk.get_text().ok());
+    Ok(text.and_then(|text| serde_json::from_str(&text).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+    use std::{fs, io::Cursor, path::PathBuf};
+
+    fn encoded_png() -> (DynamicImage, Vec<u8>) {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(5, 3, |x, y| Rgb([x as u8 * 40, y as u8 * 80, 7])));
+        let mut png = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).unwrap();
+        (img, png)
+    }
+
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("augmentator-metadata-{}-{name}", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn embedded_provenance_round_trips() {
+        let (img, png) = encoded_png();
+        let provenance = EmbeddedProvenance {
+            source: "zdjęcia/kot.png".to_string(),
+            transforms: vec![AppliedTransform {
+                name: "brightness".to_string(),
+                seed: 42,
+                params: [("delta".to_string(), -12.5)].into(),
+            }],
+            base_seed: 7,
+            seeding: SeedScheme::V1,
+            app_version: "0.0.1".to_string(),
+        };
+        let path = temp_file("round-trip.png", &embed(&png, &provenance).unwrap());
+
+        let read_back = read(&path).unwrap().expect("provenance chunk");
+        assert_eq!(
+            serde_json::to_value(&read_back).unwrap(),
+            serde_json::to_value(&provenance).unwrap()
+        );
+        // piksele bez zmian
+        assert_eq!(image::open(&path).unwrap().to_rgb8(), img.to_rgb8());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn plain_png_has_no_provenance() {
+        let (_, png) = encoded_png();
+        let path = temp_file("plain.png", &png);
+        assert!(read(&path).unwrap().is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn embed_rejects_non_png() {
+        let provenance = EmbeddedProvenance {
+            source: String::new(),
+            transforms: Vec::new(),
+            base_seed: 0,
+            seeding: SeedScheme::V2,
+            app_version: String::new(),
+        };
+        assert!(embed(b"GIF8", &provenance).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use image::DynamicImage;
+use rayon::prelude::*;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::error::AugmentError;
+use crate::execution::ExecutionOptions;
+
+// Przy 64 i więcej każde dwa hashe są „podobne”, a podział na paski traci gwarancję
+pub const MAX_DUPLICATE_THRESHOLD: u32 = 63;
+
+/// 64-bit difference hash: one bit per horizontally adjacent pair of a 9x8 grayscale thumbnail.
+pub fn dhash(img: &DynamicImage) -> u64 {
+    let small = img.thumbnail_exact(9, 8).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+// None dla plików, których nie da się zdekodować - trafią do osobnych grup.
+// Dekodujemy w puli i w budżecie pamięci runu, tak jak samą augmentację.
+pub fn dhash_all(paths: &[PathBuf], execution: &ExecutionOptions) -> Result<Vec<Option<u64>>, AugmentError> {
+    let pool = execution.thread_pool()?;
+    let io = execution.io_limits();
+    let memory = execution.memory_budget();
+    Ok(pool.install(|| {
+        paths
+            .par_iter()
+            .map(|path| {
+                let bytes = {
+                    let _read = io.reads.acquire();
+                    fs::read(path).ok()?
+                };
+                let (img, _memory) = memory.decode(path, &bytes).ok()?;
+                Some(dhash(&img))
+            })
+            .collect()
+    }))
+}
+
+/// Returns the group of every image: the smallest index of its near-duplicate cluster
+/// (hashes within `threshold` differing bits, transitively).
+pub fn group_near_duplicates(hashes: &[Option<u64>], threshold: u32) -> Vec<usize> {
+    // Z zasady szufladkowej: przy <= threshold różnicach przynajmniej
+    // jeden z threshold + 1 pasków bitów jest identyczny, więc porównujemy tylko w kubełkach
+    let bands = (threshold + 1).min(64);
+    let mut buckets: HashMap<(u32, u64), Vec<(usize, u64)>> = HashMap::new();
+
+    for (i, hash) in hashes.iter().enumerate() {
+        let Some(hash) = *hash else { continue };
+        for band in 0..bands {
+            let (start, end) = (band * 64 / bands, (band + 1) * 64 / bands);
+            let mask = (u64::MAX >> (64 - (end - start))) << start;
+            buckets.entry((band, hash & mask)).or_default().push((i, hash));
+        }
+    }
+
+    let mut groups = UnionFind::new(hashes.len());
+    for members in buckets.values() {
+        for (k, &(a, hash_a)) in members.iter().enumerate() {
+            for &(b, hash_b) in &members[k + 1..] {
+                if (hash_a ^ hash_b).count_ones() <= threshold {
+                    groups.union(a, b);
+                }
+            }
+        }
+    }
+
+    (0..hashes.len()).map(|i| groups.find(i)).collect()
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind { parent: (0..len).collect() }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut i = i;
+        while self.parent[i] != root {
+            i = std::mem::replace(&mut self.parent[i], root);
+        }
+        root
+    }
+
+    // korzeniem zostaje mniejszy indeks, żeby grupy były deterministyczne
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn dhash_follows_horizontal_gradient() {
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(90, 80, Luma([128])));
+        let rising = DynamicImage::ImageLuma8(GrayImage::from_fn(90, 80, |x, _| Luma([x as u8 * 2])));
+        let falling = DynamicImage::ImageLuma8(GrayImage::from_fn(90, 80, |x, _| Luma([255 - x as u8 * 2])));
+
+        assert_eq!(dhash(&flat), 0);
+        assert_eq!(dhash(&rising), u64::MAX);
+        assert_eq!(dhash(&falling), 0);
+    }
+
+    #[test]
+    fn groups_within_threshold_transitively() {
+        // b różni się od a jednym bitem, c od b jednym, ale od a dwoma
+        let (a, b, c) = (0b0000u64, 0b0001, 0b0011);
+        let far = u64::MAX;
+        let hashes = [Some(c), Some(far), Some(a), Some(b)];
+
+        assert_eq!(group_near_duplicates(&hashes, 1), vec![0, 1, 0, 0]);
+        assert_eq!(group_near_duplicates(&hashes, 0), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn undecodable_images_stay_alone() {
+        let hashes = [None, Some(7), None, Some(7)];
+        assert_eq!(group_near_duplicates(&hashes, 4), vec![0, 1, 2, 1]);
+    }
+
+    #[test]
+    fn max_threshold_keeps_opposite_hashes_apart() {
+        let threshold = MAX_DUPLICATE_THRESHOLD;
+        assert_eq!(group_near_duplicates(&[Some(0), Some(u64::MAX)], threshold), vec![0, 1]);
+        assert_eq!(group_near_duplicates(&[Some(0), Some(u64::MAX >> 1)], threshold), vec![0, 0]);
+    }
+}
@@ -0,0 +1,413 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::error::AugmentError;
+use crate::phash::MAX_DUPLICATE_THRESHOLD;
+
+pub struct PlannedImage {
+    pub path: PathBuf,
+    // katalog najwyższego poziomu (układ ImageFolder), "" dla obrazów w korzeniu
+    pub class: String,
+    // ile plików (poza oryginałem) powstaje z tego obrazu
+    pub outputs: usize,
+    // zależy od reguł, więc liczone osobno dla każdego obrazu
+    pub outputs_per_variant: usize,
+    pub split: Option<Split>,
+    // indeks pierwszego obrazu z grupy prawie-duplikatów (domyślnie własny)
+    group: usize,
+}
+
+impl PlannedImage {
+    pub fn variants(&self) -> usize {
+        self.outputs.div_ceil(self.outputs_per_variant)
+    }
+
+    // augmentujemy tylko zbiór treningowy
+    fn augmented(&self) -> bool {
+        self.split.is_none_or(|s| s == Split::Train)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+    Train,
+    Val,
+    Test,
+}
+
+impl Split {
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Val => "val",
+            Split::Test => "test",
+        }
+    }
+}
+
+pub struct Plan {
+    pub images: Vec<PlannedImage>,
+    pub classes: Vec<ClassCount>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceOptions {
+    // stała liczba obrazów na klasę...
+    target_count: Option<usize>,
+    // ...albo ułamek najliczniejszej klasy (domyślnie 1.0)
+    ratio: Option<f32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitOptions {
+    train: f32,
+    val: f32,
+    test: f32,
+    // maks. odległość Hamminga dHash, przy której obrazy lądują w tym samym zbiorze
+    pub duplicate_threshold: Option<u32>,
+}
+
+impl BalanceOptions {
+    pub fn validate(&self) -> Result<(), AugmentError> {
+        match (self.target_count, self.ratio) {
+            (None, Some(ratio)) if !(ratio.is_finite() && ratio > 0.0) => Err(AugmentError::InvalidOptions(
+                format!("Balance ratio must be positive, got {ratio}."),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SplitOptions {
+    pub fn validate(&self) -> Result<(), AugmentError> {
+        let ratios = [self.train, self.val, self.test];
+        let sum: f32 = ratios.iter().sum();
+        if ratios.iter().any(|r| !r.is_finite() || *r < 0.0) || sum <= 0.0 {
+            return Err(AugmentError::InvalidOptions(format!(
+                "Split ratios must be non-negative and not all zero, got {}/{}/{}.",
+                self.train, self.val, self.test
+            )));
+        }
+        if let Some(threshold) = self.duplicate_threshold.filter(|t| *t > MAX_DUPLICATE_THRESHOLD) {
+            return Err(AugmentError::InvalidOptions(format!(
+                "Duplicate threshold must be at most {MAX_DUPLICATE_THRESHOLD} differing bits, got {threshold}."
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// `target` counts every output image, so it can't be below the number of inputs.
+pub fn validate_target_total(target: usize, inputs: usize) -> Result<(), AugmentError> {
+    if target < inputs {
+        return Err(AugmentError::InvalidOptions(format!(
+            "Target of {target} images is smaller than the {inputs} source images."
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct SplitEntry {
+    path: String,
+    class: String,
+    split: Split,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    split: Option<Split>,
+    members: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassCount {
+    pub class: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanSummary {
+    pub inputs: usize,
+    pub generated: usize,
+    pub total: usize,
+    pub min_per_image: usize,
+    pub max_per_image: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<ClassCount>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub splits: BTreeMap<Split, usize>,
+}
+
+impl Plan {
+    /// `outputs_per_variant` gets each image's path relative to `input_dir` and its class.
+    pub fn new<F>(paths: Vec<PathBuf>, input_dir: &Path, outputs_per_variant: F) -> Self
+    where
+        F: Fn(&Path, &str) -> usize,
+    {
+        let images = paths
+            .into_iter()
+            .enumerate()
+            .map(|(group, path)| {
+                let relative = path.strip_prefix(input_dir).unwrap_or(&path);
+                let class = class_of(relative);
+                PlannedImage {
+                    outputs_per_variant: outputs_per_variant(relative, &class).max(1),
+                    class,
+                    path,
+                    outputs: 0,
+                    split: None,
+                    group,
+                }
+            })
+            .collect();
+
+        Plan { images, classes: Vec::new() }
+    }
+
+    /// `groups` comes from `phash::group_near_duplicates`, one entry per image.
+    pub fn with_duplicate_groups(mut self, groups: Vec<usize>) -> Self {
+        for (image, group) in self.images.iter_mut().zip(groups) {
+            image.group = group;
+        }
+        self
+    }
+
+    /// Stratified by class: every class is shuffled with the seed and cut by the (normalized) ratios.
+    /// Near-duplicate groups are never split apart.
+    pub fn with_split(mut self, split: &SplitOptions, seed: u64) -> Result<Self, AugmentError> {
+        split.validate()?;
+        let sum = split.train + split.val + split.test;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        for mut groups in self.groups_by_class().into_values() {
+            groups.shuffle(&mut rng);
+
+            let count = groups.iter().map(Vec::len).sum::<usize>();
+            let n = count as f32;
+            let test = (n * split.test / sum).round() as usize;
+            let val = ((n * split.val / sum).round() as usize).min(count - test);
+
+            let mut assigned = 0;
+            for group in groups {
+                let target = if assigned < test {
+                    Split::Test
+                } else if assigned < test + val {
+                    Split::Val
+                } else {
+                    Split::Train
+                };
+                assigned += group.len();
+                for i in group {
+                    self.images[i].split = Some(target);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn with_variants(mut self, variants: u32) -> Self {
+        for image in self.images.iter_mut().filter(|i| i.augmented()) {
+            image.outputs = image.outputs_per_variant * variants as usize;
+        }
+        self
+    }
+
+    /// `target` counts every output image, including the originals of all splits.
+    pub fn with_target_total(mut self, target: usize, seed: u64) -> Result<Self, AugmentError> {
+        let inputs = self.images.len();
+        let augmented: Vec<usize> = (0..inputs).filter(|&i| self.images[i].augmented()).collect();
+        if augmented.is_empty() {
+            return Err(AugmentError::InvalidOptions("There are no images to augment.".into()));
+        }
+        validate_target_total(target, inputs)?;
+
+        let outputs = distribute(target - inputs, augmented.len(), &mut StdRng::seed_from_u64(seed));
+        for (&i, outputs) in augmented.iter().zip(outputs) {
+            self.images[i].outputs = outputs;
+        }
+        Ok(self)
+    }
+
+    /// Oversamples every class below the target; classes already above it only get their originals.
+    pub fn with_balance(mut self, balance: &BalanceOptions, seed: u64) -> Result<Self, AugmentError> {
+        balance.validate()?;
+        let mut by_class = self.indices_by_class();
+        for indices in by_class.values_mut() {
+            indices.retain(|&i| self.images[i].augmented());
+        }
+        by_class.retain(|_, indices| !indices.is_empty());
+
+        let largest = by_class.values().map(Vec::len).max().unwrap_or(0);
+        if largest == 0 {
+            return Err(AugmentError::InvalidOptions(
+                "There are no images to augment.".into(),
+            ));
+        }
+        let target = match (balance.target_count, balance.ratio) {
+            (Some(count), _) => count,
+            (None, Some(ratio)) => (largest as f32 * ratio).ceil() as usize,
+            (None, None) => largest,
+        };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        for (class, indices) in by_class {
+            let before = indices.len();
+            let extra = target.saturating_sub(before);
+
+            let outputs = distribute(extra, before, &mut rng);
+            for (&i, outputs) in indices.iter().zip(outputs) {
+                self.images[i].outputs = outputs;
+            }
+            self.classes.push(ClassCount { class, before, after: before + extra });
+        }
+        Ok(self)
+    }
+
+    pub fn split_manifest(&self, input_dir: &Path) -> Vec<SplitEntry> {
+        self.images
+            .iter()
+            .filter_map(|image| {
+                Some(SplitEntry {
+                    path: relative_name(&image.path, input_dir),
+                    class: image.class.clone(),
+                    split: image.split?,
+                })
+            })
+            .collect()
+    }
+
+    // tylko grupy z więcej niż jednym obrazem
+    pub fn duplicate_groups(&self, input_dir: &Path) -> Vec<DuplicateGroup> {
+        self.groups()
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|members| DuplicateGroup {
+                split: self.images[members[0]].split,
+                members: members
+                    .iter()
+                    .map(|&i| relative_name(&self.images[i].path, input_dir))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn groups(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, image) in self.images.iter().enumerate() {
+            groups.entry(image.group).or_default().push(i);
+        }
+        groups
+    }
+
+    // grupa należy do klasy swojego pierwszego obrazu
+    fn groups_by_class(&self) -> BTreeMap<String, Vec<Vec<usize>>> {
+        let mut by_class: BTreeMap<String, Vec<Vec<usize>>> = BTreeMap::new();
+        for (first, members) in self.groups() {
+            by_class
+                .entry(self.images[first].class.clone())
+                .or_default()
+                .push(members);
+        }
+        by_class
+    }
+
+    fn indices_by_class(&self) -> BTreeMap<String, Vec<usize>> {
+        let mut by_class: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, image) in self.images.iter().enumerate() {
+            by_class.entry(image.class.clone()).or_default().push(i);
+        }
+        by_class
+    }
+
+    // Numerujemy nazwy dopiero gdy któryś obraz ma więcej niż jeden wariant
+    pub fn numbered(&self) -> bool {
+        self.images.iter().any(|i| i.variants() > 1)
+    }
+
+    pub fn summary(&self) -> PlanSummary {
+        let generated = self.images.iter().map(|i| i.outputs).sum::<usize>();
+        PlanSummary {
+            inputs: self.images.len(),
+            generated,
+            total: self.images.len() + generated,
+            min_per_image: self.images.iter().map(|i| i.outputs).min().unwrap_or(0),
+            max_per_image: self.images.iter().map(|i| i.outputs).max().unwrap_or(0),
+            classes: self.classes.clone(),
+            splits: self.images.iter().filter_map(|i| i.split).fold(
+                BTreeMap::new(),
+                |mut counts, split| {
+                    *counts.entry(split).or_default() += 1;
+                    counts
+                },
+            ),
+        }
+    }
+}
+
+// `extra` rozkładamy równo, resztę dostaje losowy (seedowany) podzbiór
+fn distribute(extra: usize, count: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut outputs = vec![extra / count; count];
+
+    let mut order: Vec<usize> = (0..count).collect();
+    order.shuffle(rng);
+    for &i in &order[..extra % count] {
+        outputs[i] += 1;
+    }
+    outputs
+}
+
+fn relative_name(path: &Path, input_dir: &Path) -> String {
+    path.strip_prefix(input_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Top-level directory of a path relative to the input, or "" for images directly in it.
+pub fn class_of(relative: &Path) -> String {
+    let mut components = relative.components();
+
+    match (components.next(), components.next()) {
+        (Some(class), Some(_)) => class.as_os_str().to_string_lossy().into_owned(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribute_cases() {
+        // (extra, count): każdy dostaje extra / count, reszta po jednym
+        let cases = [(0, 3), (3, 3), (7, 3), (2, 5), (10, 1), (100, 7)];
+        for (extra, count) in cases {
+            let outputs = distribute(extra, count, &mut StdRng::seed_from_u64(1));
+
+            assert_eq!(outputs.len(), count, "{extra}/{count}");
+            assert_eq!(outputs.iter().sum::<usize>(), extra, "{extra}/{count}");
+            let even = extra / count;
+            assert!(outputs.iter().all(|&o| o == even || o == even + 1), "{extra}/{count}: {outputs:?}");
+            assert_eq!(outputs.iter().filter(|&&o| o > even).count(), extra % count, "{extra}/{count}");
+        }
+    }
+
+    #[test]
+    fn distribute_is_seeded() {
+        let run = |seed| distribute(5, 12, &mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(run(42), run(42));
+        assert!((0..10).any(|seed| run(seed) != run(42)));
+    }
+}
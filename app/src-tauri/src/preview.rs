@@ -0,0 +1,121 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::{
+    fs,
+    io::Cursor,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::error::AugmentError;
+use crate::execution::MemoryBudget;
+use crate::plan::class_of;
+use crate::provenance::AppliedTransform;
+use crate::seeding::derive_seed;
+use crate::transformation_factory::TransformationFactory;
+use crate::Recipe;
+
+pub const DEFAULT_PREVIEW_SIZE: u32 = 256;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preview {
+    // nazwa pliku, jaką dostałoby wyjście, np. `shifted` albo `mirror`; pusta dla oryginału
+    pub suffix: String,
+    pub transforms: Vec<AppliedTransform>,
+    // data URL z PNG
+    pub image: String,
+}
+
+// Numer najnowszego zapytania; starsze przerywamy między krokami, zamiast liczyć je do końca
+static LATEST_REQUEST: AtomicU64 = AtomicU64::new(0);
+
+/// Marks a new preview request, superseding the ones still rendering.
+pub fn begin_request() -> u64 {
+    LATEST_REQUEST.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// First variant of every output for `sample`. Parameters are sampled on the full image, so they're
+/// the ones the run will use, then applied to a thumbnail so it refreshes quickly. Returns nothing
+/// once `request` is superseded; the UI ignores stale responses anyway.
+pub fn render(
+    sample: &Path,
+    relative_path: &Path,
+    recipe: &Recipe,
+    memory: &MemoryBudget,
+    max_size: u32,
+    request: u64,
+) -> Result<Vec<Preview>, AugmentError> {
+    let superseded = || LATEST_REQUEST.load(Ordering::Relaxed) != request;
+    let bytes = fs::read(sample).map_err(|source| AugmentError::Read { path: sample.to_path_buf(), source })?;
+    let (img, _memory) = memory.decode(sample, &bytes)?;
+    drop(bytes);
+    let thumb = img.thumbnail(max_size, max_size);
+    let (always, one_time) = recipe.resolve(relative_path, &class_of(relative_path));
+    let factory = TransformationFactory::new();
+
+    let transform_error = |name: &str, message: String| AugmentError::Transform {
+        path: sample.to_path_buf(),
+        transform: name.to_string(),
+        message,
+    };
+    // jak `apply_transformation` w runie: parametry z pełnego obrazu, wynik na miniaturze
+    let transform = |full: &DynamicImage, thumb: &DynamicImage, name: &str| {
+        let transformation = factory
+            .create(name)
+            .ok_or_else(|| transform_error(name, format!("Transformation '{name}' not implemented.")))?;
+        let seed = derive_seed(recipe.seeding, relative_path, recipe.seed, name, 0);
+        let params = transformation.sample(full, &mut StdRng::seed_from_u64(seed));
+        let scaled = transformation.scale(&params, full.dimensions(), thumb.dimensions());
+        let result = transformation.apply_with(thumb, &scaled).map_err(|e| transform_error(name, e.to_string()))?;
+        Ok::<_, AugmentError>((transformation, result, AppliedTransform { name: name.to_string(), seed, params }))
+    };
+
+    let mut previews = vec![preview(String::new(), Vec::new(), &thumb)?];
+
+    let (mut full_base, mut base) = (img, thumb);
+    let mut chain = Vec::new();
+    for name in &always {
+        if superseded() {
+            return Ok(Vec::new());
+        }
+        let (transformation, shifted, applied) = transform(&full_base, &base, name)?;
+        // kolejne parametry losujemy z przesuniętego pełnego obrazu, jak w runie
+        if !one_time.is_empty() {
+            full_base = transformation
+                .apply_with(&full_base, &applied.params)
+                .map_err(|e| transform_error(name, e.to_string()))?;
+        }
+        base = shifted;
+        chain.push(applied);
+    }
+
+    if one_time.is_empty() {
+        previews.push(preview("shifted".to_string(), chain, &base)?);
+        return Ok(previews);
+    }
+    for name in one_time {
+        if superseded() {
+            return Ok(Vec::new());
+        }
+        let (_, transformed, applied) = transform(&full_base, &base, name)?;
+        let mut transforms = chain.clone();
+        transforms.push(applied);
+        previews.push(preview(name.to_string(), transforms, &transformed)?);
+    }
+    Ok(previews)
+}
+
+fn preview(suffix: String, transforms: Vec<AppliedTransform>, img: &DynamicImage) -> Result<Preview, AugmentError> {
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| AugmentError::Internal(e.to_string()))?;
+
+    Ok(Preview {
+        suffix,
+        transforms,
+        image: format!("data:image/png;base64,{}", STANDARD.encode(png)),
+    })
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::seeding::SeedScheme;
+use crate::transformations::Params;
+
+const PROVENANCE_FILE: &str = "provenance.jsonl";
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedTransform {
+    pub name: String,
+    pub seed: u64,
+    // wylosowane wartości, wystarczą do odtworzenia bez seeda
+    #[serde(default, skip_serializing_if = "Params::is_empty")]
+    pub params: Params,
+}
+
+/// Where a single output file came from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceEntry {
+    // względem katalogu wejściowego
+    pub source: String,
+    // względem katalogu wyjściowego
+    pub output: String,
+    // None dla kopii oryginału
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<u32>,
+    // w kolejności zastosowania, pusta dla kopii oryginału
+    pub transforms: Vec<AppliedTransform>,
+    pub base_seed: u64,
+    pub seeding: SeedScheme,
+    pub source_hash: String,
+    pub output_hash: String,
+    pub app_version: String,
+}
+
+/// `provenance.jsonl` in the output directory, one line per output file. Lines are appended
+/// as images finish, so the manifest survives a crash; `write` sorts it by output path.
+pub struct Manifest {
+    output_dir: PathBuf,
+    state: Mutex<ManifestState>,
+}
+
+struct ManifestState {
+    entries: BTreeMap<String, ProvenanceEntry>,
+    // prawdziwe ścieżki wyjść z tego runu; `output` jest stratny dla nazw nie-UTF-8
+    paths: HashMap<String, PathBuf>,
+    // wyjścia należące do tego runu: zapisane teraz albo zachowane z poprzedniego
+    run_outputs: BTreeSet<String>,
+    file: File,
+}
+
+impl Manifest {
+    pub fn open(output_dir: &Path) -> io::Result<Self> {
+        // wpisy z poprzednich runów zostają dla obrazów pominiętych według dziennika
+        let path = output_dir.join(PROVENANCE_FILE);
+        let mut entries: BTreeMap<String, ProvenanceEntry> =
+            read_entries(output_dir)?.into_iter().map(|entry| (entry.output.clone(), entry)).collect();
+
+        // przepisujemy od razu, żeby dopisywanie nie zaczęło się od urwanej linii po crashu
+        write_entries(output_dir, &mut entries, &HashMap::new())?;
+        let file = OpenOptions::new().append(true).open(&path)?;
+        Ok(Manifest {
+            output_dir: output_dir.to_path_buf(),
+            state: Mutex::new(ManifestState {
+                entries,
+                paths: HashMap::new(),
+                run_outputs: BTreeSet::new(),
+                file,
+            }),
+        })
+    }
+
+    /// Appends entries for outputs written to `path`s. Call before the image is journaled
+    /// as finished, so a skipped image always has its entries.
+    pub fn record(&self, new_entries: Vec<(PathBuf, ProvenanceEntry)>) -> io::Result<()> {
+        let mut lines = String::new();
+        for (_, entry) in &new_entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.file.write_all(lines.as_bytes())?;
+        state.file.flush()?;
+        for (path, entry) in new_entries {
+            state.run_outputs.insert(entry.output.clone());
+            state.paths.insert(entry.output.clone(), path);
+            state.entries.insert(entry.output.clone(), entry);
+        }
+        Ok(())
+    }
+
+    /// Counts `outputs` written by an earlier run as part of this one, e.g. for images
+    /// skipped as up to date or files kept by the conflict policy.
+    pub fn keep<'a>(&self, outputs: impl IntoIterator<Item = &'a String>) {
+        self.state.lock().unwrap().run_outputs.extend(outputs.into_iter().cloned());
+    }
+
+    /// Entries of the outputs recorded or kept during this run, without leftovers of earlier runs.
+    pub fn into_run_entries(self) -> Vec<ProvenanceEntry> {
+        let ManifestState { mut entries, run_outputs, .. } = self.state.into_inner().unwrap();
+        run_outputs.iter().filter_map(|output| entries.remove(output)).collect()
+    }
+
+    /// Rewrites the manifest sorted and without duplicates, dropping entries whose output file
+    /// no longer exists.
+    pub fn write(&self) -> io::Result<()> {
+        let state = &mut *self.state.lock().unwrap();
+        write_entries(&self.output_dir, &mut state.entries, &state.paths)
+    }
+}
+
+/// Parses `provenance.jsonl` in `output_dir` without touching it, so it works on read-only
+/// directories. Later lines for the same output win; a missing manifest has no entries.
+pub fn read_entries(output_dir: &Path) -> io::Result<Vec<ProvenanceEntry>> {
+    let mut entries = BTreeMap::new();
+    if let Ok(file) = File::open(output_dir.join(PROVENANCE_FILE)) {
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str::<ProvenanceEntry>(&line?) {
+                entries.insert(entry.output.clone(), entry);
+            }
+        }
+    }
+    Ok(entries.into_values().collect())
+}
+
+fn write_entries(
+    output_dir: &Path,
+    entries: &mut BTreeMap<String, ProvenanceEntry>,
+    paths: &HashMap<String, PathBuf>,
+) -> io::Result<()> {
+    let path = output_dir.join(PROVENANCE_FILE);
+    let temp_path = output_dir.join(format!("{PROVENANCE_FILE}.tmp"));
+
+    entries.retain(|output, _| match paths.get(output) {
+        Some(path) => path.is_file(),
+        // wpis z poprzedniego runu: nazwy, której nie da się odtworzyć ze stratnego zapisu, nie sprawdzamy
+        None => output.contains(char::REPLACEMENT_CHARACTER) || output_dir.join(output).is_file(),
+    });
+    let mut file = BufWriter::new(File::create(&temp_path)?);
+    for entry in entries.values() {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+    file.into_inner()?.sync_all()?;
+    fs::rename(&temp_path, &path)
+}
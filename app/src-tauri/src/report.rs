@@ -0,0 +1,177 @@
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path};
+
+use crate::error::AugmentError;
+use crate::execution::MemoryBudget;
+use crate::plan::PlanSummary;
+use crate::provenance::{ProvenanceEntry, APP_VERSION};
+use crate::RunSummary;
+
+const REPORT_FILE: &str = "report.html";
+pub const CONTACT_SHEET_FILE: &str = "contact_sheet.png";
+const CELL_SIZE: u32 = 128;
+const MAX_COLUMNS: usize = 8;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReportOptions {
+    // ile wejść pokazujemy
+    pub samples: usize,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self { samples: 12 }
+    }
+}
+
+/// Run statistics shown at the top of the report.
+pub struct ReportStats<'a> {
+    pub plan: &'a PlanSummary,
+    pub run: &'a RunSummary,
+    pub seed: u64,
+}
+
+/// Writes `report.html` and `contact_sheet.png` for a seeded sample of inputs: the original copy
+/// followed by every augmented output, with transform names and sampled parameters.
+pub fn write(
+    output_dir: &Path,
+    entries: Vec<ProvenanceEntry>,
+    stats: &ReportStats,
+    options: &ReportOptions,
+    memory: &MemoryBudget,
+) -> Result<(), AugmentError> {
+    let mut by_source: BTreeMap<String, Vec<ProvenanceEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_source.entry(entry.source.clone()).or_default().push(entry);
+    }
+    for outputs in by_source.values_mut() {
+        // oryginał (bez transformacji) pierwszy, potem warianty po kolei
+        outputs.sort_by(|a, b| (a.variant, &a.output).cmp(&(b.variant, &b.output)));
+    }
+
+    let mut sources: Vec<&String> = by_source.keys().collect();
+    sources.shuffle(&mut StdRng::seed_from_u64(stats.seed));
+    sources.truncate(options.samples);
+    sources.sort();
+    let rows: Vec<&[ProvenanceEntry]> = sources.iter().map(|s| by_source[*s].as_slice()).collect();
+
+    let html = render_html(&rows, stats, by_source.len());
+    let path = output_dir.join(REPORT_FILE);
+    fs::write(&path, html).map_err(|source| AugmentError::Write { path, source })?;
+
+    let path = output_dir.join(CONTACT_SHEET_FILE);
+    contact_sheet(output_dir, &rows, memory)
+        .save(&path)
+        .map_err(|source| AugmentError::Save { path, source })
+}
+
+fn render_html(rows: &[&[ProvenanceEntry]], stats: &ReportStats, sources: usize) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Augmentation report</title>\n\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px;vertical-align:top}}\
+         figure{{display:inline-block;margin:4px;width:{CELL_SIZE}px;font-size:11px}}\
+         img{{max-width:{CELL_SIZE}px;max-height:{CELL_SIZE}px}}</style></head><body>\n\
+         <h1>Augmentation report</h1>\n<table>\n\
+         <tr><th>Source images</th><td>{}</td></tr>\n<tr><th>Generated</th><td>{}</td></tr>\n\
+         <tr><th>Total</th><td>{}</td></tr>\n<tr><th>Processed</th><td>{}</td></tr>\n\
+         <tr><th>Skipped (up to date)</th><td>{}</td></tr>\n<tr><th>Failed images</th><td>{}</td></tr>\n\
+         <tr><th>Seed</th><td>{}</td></tr>\n<tr><th>Version</th><td>{APP_VERSION}</td></tr>\n</table>\n",
+        stats.plan.inputs,
+        stats.plan.generated,
+        stats.plan.total,
+        stats.run.processed,
+        stats.run.skipped,
+        stats.run.failed,
+        stats.seed,
+    );
+
+    if !stats.plan.classes.is_empty() {
+        html.push_str("<h2>Classes</h2>\n<table><tr><th>Class</th><th>Before</th><th>After</th></tr>\n");
+        for class in &stats.plan.classes {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(if class.class.is_empty() { "." } else { &class.class }),
+                class.before,
+                class.after
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let _ = writeln!(html, "<h2>Sample of {} / {} source images</h2>", rows.len(), sources);
+    for outputs in rows {
+        let _ = writeln!(html, "<h3>{}</h3><div>", escape(&outputs[0].source));
+        for entry in outputs.iter() {
+            let caption = if entry.transforms.is_empty() {
+                "original".to_string()
+            } else {
+                entry.transforms.iter().map(describe).collect::<Vec<_>>().join(" → ")
+            };
+            let _ = writeln!(
+                html,
+                "<figure><img src=\"{}\" loading=\"lazy\"><figcaption>{}</figcaption></figure>",
+                url_path(&entry.output),
+                escape(&caption)
+            );
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+// `brightness (delta 34)`
+fn describe(transform: &crate::provenance::AppliedTransform) -> String {
+    if transform.params.is_empty() {
+        return transform.name.clone();
+    }
+    let params: Vec<String> = transform
+        .params
+        .iter()
+        .map(|(name, value)| format!("{name} {}", (value * 100.0).round() / 100.0))
+        .collect();
+    format!("{} ({})", transform.name, params.join(", "))
+}
+
+// Jeden wiersz na wejście: oryginał, potem warianty; brakujące i zbyt duże pliki zostają pustą komórką
+fn contact_sheet(output_dir: &Path, rows: &[&[ProvenanceEntry]], memory: &MemoryBudget) -> RgbaImage {
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0).clamp(1, MAX_COLUMNS) as u32;
+    let mut sheet = RgbaImage::from_pixel(columns * CELL_SIZE, rows.len().max(1) as u32 * CELL_SIZE, Rgba([255; 4]));
+
+    for (row, outputs) in rows.iter().enumerate() {
+        for (column, entry) in outputs.iter().take(MAX_COLUMNS).enumerate() {
+            let path = output_dir.join(&entry.output);
+            let Ok(bytes) = fs::read(&path) else { continue };
+            let Ok((img, _memory)) = memory.decode(&path, &bytes) else { continue };
+            let thumb: DynamicImage = img.thumbnail(CELL_SIZE, CELL_SIZE);
+            let x = column as u32 * CELL_SIZE + (CELL_SIZE - thumb.width()) / 2;
+            let y = row as u32 * CELL_SIZE + (CELL_SIZE - thumb.height()) / 2;
+            imageops::overlay(&mut sheet, &thumb.to_rgba8(), x as i64, y as i64);
+        }
+    }
+    sheet
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Ścieżka względna jako URL: '/' zostaje, reszta spoza bezpiecznych znaków jest kodowana
+fn url_path(path: &str) -> String {
+    let mut url = String::new();
+    for byte in path.replace('\\', "/").bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            let _ = write!(url, "%{byte:02X}");
+        }
+    }
+    url
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransformRule {
+    // glob na ścieżce względnej, np. "signs/left_*/**" lub "**/*_ripe.png"
+    pub pattern: Option<String>,
+    // katalog klasy (najwyższego poziomu)
+    pub class: Option<String>,
+    // zastępuje cały wybór transformacji
+    pub only: Option<Vec<String>>,
+    pub enable: Vec<String>,
+    pub disable: Vec<String>,
+}
+
+impl TransformRule {
+    pub fn transformations(&self) -> impl Iterator<Item = &str> {
+        self.only
+            .iter()
+            .flatten()
+            .chain(&self.enable)
+            .chain(&self.disable)
+            .map(String::as_str)
+    }
+
+    fn matches(&self, relative: &str, class: &str) -> bool {
+        self.class.as_deref().is_none_or(|c| c == class)
+            && self.pattern.as_deref().is_none_or(|p| glob_match(p, relative))
+    }
+}
+
+/// Applies every matching rule in order on top of the selected transformations.
+pub fn resolve<'a>(
+    selection: &'a [String],
+    rules: &'a [TransformRule],
+    relative_path: &Path,
+    class: &str,
+) -> Vec<&'a str> {
+    let relative = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut active: Vec<&str> = selection.iter().map(String::as_str).collect();
+    for rule in rules.iter().filter(|r| r.matches(&relative, class)) {
+        if let Some(only) = &rule.only {
+            active = only.iter().map(String::as_str).collect();
+        }
+        active.retain(|t| !rule.disable.iter().any(|d| d == t));
+        for t in &rule.enable {
+            if !active.contains(&t.as_str()) {
+                active.push(t);
+            }
+        }
+    }
+    active
+}
+
+// `*` i `?` nie przechodzą przez '/', `**` tak (także zero katalogów w `**/`)
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    glob_match_from(&pattern, &path)
+}
+
+fn glob_match_from(p: &[char], s: &[char]) -> bool {
+    match p.first() {
+        None => s.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            let rest_without_slash = rest.strip_prefix(&['/'][..]).unwrap_or(rest);
+
+            glob_match_from(rest_without_slash, s)
+                || (0..=s.len()).any(|i| glob_match_from(rest, &s[i..]))
+        }
+        Some('*') => (0..=s.len())
+            .take_while(|&i| i == 0 || s[i - 1] != '/')
+            .any(|i| glob_match_from(&p[1..], &s[i..])),
+        Some('?') => s.first().is_some_and(|&c| c != '/') && glob_match_from(&p[1..], &s[1..]),
+        Some(c) => s.first() == Some(c) && glob_match_from(&p[1..], &s[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_cases() {
+        let cases = [
+            ("*.png", "cat.png", true),
+            ("*.png", "cats/cat.png", false),
+            ("cat?.png", "cat1.png", true),
+            ("cat?.png", "cat.png", false),
+            ("a?b", "a/b", false),
+            ("fruit/*", "fruit/apple.png", true),
+            ("fruit/*", "fruit/red/apple.png", false),
+            ("fruit/**", "fruit/red/apple.png", true),
+            ("fruit/**", "fruit", false),
+            ("**/apple.png", "apple.png", true),
+            ("**/apple.png", "fruit/red/apple.png", true),
+            ("fruit/**/apple.png", "fruit/apple.png", true),
+            ("fruit/**/apple.png", "fruit/red/big/apple.png", true),
+            ("fruit/**/apple.png", "veg/apple.png", false),
+            ("**", "anything/at/all.png", true),
+            ("", "", true),
+            ("", "a", false),
+            ("Cat.png", "cat.png", false),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(glob_match(pattern, path), expected, "{pattern} vs {path}");
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::error::AugmentError;
+use crate::provenance::{ProvenanceEntry, APP_VERSION};
+use crate::seeding::SeedScheme;
+use crate::AugmentOptions;
+
+const RUN_RECORD_FILE: &str = "run_record.json";
+const REPRODUCTION_REPORT_FILE: &str = "reproduction_report.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedFile {
+    pub path: String,
+    pub hash: String,
+}
+
+/// Everything needed to re-run an augmentation and check that it produced the same files.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    pub app_version: String,
+    pub input: String,
+    pub transformations: Vec<String>,
+    pub seed: u64,
+    pub seeding: SeedScheme,
+    pub options: AugmentOptions,
+    // względem katalogu wejściowego
+    pub inputs: Vec<RecordedFile>,
+    // względem katalogu wyjściowego
+    pub outputs: Vec<RecordedFile>,
+}
+
+impl RunRecord {
+    pub fn new(input: &str, transformations: &[String], seed: u64, options: &AugmentOptions) -> Self {
+        RunRecord {
+            app_version: APP_VERSION.to_string(),
+            input: input.to_string(),
+            transformations: transformations.to_vec(),
+            seed,
+            seeding: options.seeding,
+            options: options.clone(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self, AugmentError> {
+        let path = dir.join(RUN_RECORD_FILE);
+        let text = fs::read_to_string(&path).map_err(|source| AugmentError::Read { path: path.clone(), source })?;
+        serde_json::from_str(&text)
+            .map_err(|e| AugmentError::InvalidOptions(format!("Run record {} is invalid: {e}", path.display())))
+    }
+
+    /// Fills inputs and outputs from this run's provenance entries and writes the record
+    /// to the output directory.
+    pub fn complete(mut self, output_dir: &Path, entries: &[ProvenanceEntry]) -> Result<Self, AugmentError> {
+        let mut inputs = BTreeMap::new();
+        for entry in entries {
+            inputs.insert(entry.source.clone(), entry.source_hash.clone());
+            self.outputs.push(RecordedFile { path: entry.output.clone(), hash: entry.output_hash.clone() });
+        }
+        self.inputs = inputs.into_iter().map(|(path, hash)| RecordedFile { path, hash }).collect();
+
+        let path = output_dir.join(RUN_RECORD_FILE);
+        let json = serde_json::to_string_pretty(&self).map_err(|e| AugmentError::Internal(e.to_string()))?;
+        fs::write(&path, json).map_err(|source| AugmentError::Write { path, source })?;
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashMismatch {
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+    // czy odtworzenie zapisanych parametrów daje zapisany plik; wtedy różni się tylko losowanie
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replayed: Option<bool>,
+}
+
+/// Differences between a recorded run and its reproduction. Empty lists mean the outputs are identical.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReproductionReport {
+    pub matched: usize,
+    pub changed_inputs: Vec<HashMismatch>,
+    pub missing_inputs: Vec<String>,
+    pub mismatched: Vec<HashMismatch>,
+    // w zapisie, ale nie powstały
+    pub missing: Vec<String>,
+    // powstały, ale nie ma ich w zapisie
+    pub unexpected: Vec<String>,
+    // wersja aplikacji, jeśli inna niż w zapisie
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_version: Option<String>,
+}
+
+impl ReproductionReport {
+    pub fn compare(expected: &RunRecord, actual: &RunRecord) -> Self {
+        let (changed_inputs, missing_inputs, _) = diff(&expected.inputs, &actual.inputs);
+        let (mismatched, missing, unexpected) = diff(&expected.outputs, &actual.outputs);
+
+        ReproductionReport {
+            matched: expected.outputs.len() - mismatched.len() - missing.len(),
+            changed_inputs,
+            missing_inputs,
+            mismatched,
+            missing,
+            unexpected,
+            recorded_version: (expected.app_version != actual.app_version).then(|| expected.app_version.clone()),
+        }
+    }
+
+    /// Replays every mismatched output from its recorded parameters. `replay` returns the hash
+    /// of the replayed file, or `None` when the output can't be replayed.
+    pub fn check_replays(&mut self, replay: impl Fn(&str) -> Option<String>) {
+        for mismatch in &mut self.mismatched {
+            mismatch.replayed = replay(&mismatch.path).map(|hash| hash == mismatch.expected);
+        }
+    }
+
+    pub fn write(&self, output_dir: &Path) -> Result<(), AugmentError> {
+        let path = output_dir.join(REPRODUCTION_REPORT_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| AugmentError::Internal(e.to_string()))?;
+        fs::write(&path, json).map_err(|source| AugmentError::Write { path, source })
+    }
+}
+
+// (inne hashe, brakujące, nadmiarowe)
+fn diff(expected: &[RecordedFile], actual: &[RecordedFile]) -> (Vec<HashMismatch>, Vec<String>, Vec<String>) {
+    let actual_hashes: BTreeMap<&str, &str> = actual.iter().map(|f| (f.path.as_str(), f.hash.as_str())).collect();
+    let expected_paths: HashSet<&str> = expected.iter().map(|f| f.path.as_str()).collect();
+
+    let mut mismatched = Vec::new();
+    let mut missing = Vec::new();
+    for file in expected {
+        match actual_hashes.get(file.path.as_str()) {
+            Some(&hash) if hash == file.hash => {}
+            Some(&hash) => mismatched.push(HashMismatch {
+                path: file.path.clone(),
+                expected: file.hash.clone(),
+                actual: hash.to_string(),
+                replayed: None,
+            }),
+            None => missing.push(file.path.clone()),
+        }
+    }
+    let unexpected = actual
+        .iter()
+        .filter(|f| !expected_paths.contains(f.path.as_str()))
+        .map(|f| f.path.clone())
+        .collect();
+    (mismatched, missing, unexpected)
+}
@@ -0,0 +1,125 @@
+use blake3::Hasher;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+/// How per-image transformation seeds are derived. Kept selectable so older runs stay reproducible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedScheme {
+    // lowercased file stem, taki sam dla `train/cat/001.png` i `val/dog/001.png`
+    V1,
+    // bajty pełnej ścieżki względnej
+    #[default]
+    V2,
+}
+
+pub fn derive_seed(scheme: SeedScheme, relative_path: &Path, base_seed: u64, transform: &str, variant: u32) -> u64 {
+    let digest = match scheme {
+        SeedScheme::V1 => {
+            // nie-UTF-8 stem daje pusty string, jak w wersji 1
+            let stem = relative_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_lowercase())
+                .unwrap_or_default();
+
+            let mut h = Hasher::new();
+            h.update(&base_seed.to_le_bytes());
+            h.update(stem.as_bytes());
+            h.update(transform.as_bytes());
+            // wariant 0 zostaje bez indeksu, żeby stare seedy się nie zmieniły
+            if variant > 0 {
+                h.update(&variant.to_le_bytes());
+            }
+            h.finalize()
+        }
+        SeedScheme::V2 => {
+            let mut h = Hasher::new();
+            h.update(b"augmentator seed v2\0");
+            h.update(&base_seed.to_le_bytes());
+            h.update(&path_bytes(relative_path));
+            h.update(&[0]);
+            h.update(transform.as_bytes());
+            h.update(&[0]);
+            h.update(&variant.to_le_bytes());
+            h.finalize()
+        }
+    };
+
+    let mut eight = [0u8; 8];
+    eight.copy_from_slice(&digest.as_bytes()[..8]);
+    u64::from_le_bytes(eight)
+}
+
+// Komponenty złączone przez '/', żeby ta sama ścieżka dawała ten sam seed na każdym systemie
+fn path_bytes(relative_path: &Path) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for component in relative_path.components() {
+        let Component::Normal(name) = component else { continue };
+        if !bytes.is_empty() {
+            bytes.push(b'/');
+        }
+        // na Unixie surowe bajty nazwy, na Windowsie WTF-8, w obu przypadkach bezstratnie
+        bytes.extend_from_slice(name.as_encoded_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // wartości starego derive_seed_for_transform(stem, seed, transform, variant)
+    #[test]
+    fn v1_matches_legacy_seeds() {
+        let cases = [
+            ("cat.png", 42, "mirror", 0, 6937311600622872891),
+            ("cat.png", 42, "mirror", 1, 6855448093567007750),
+            ("cat.png", 42, "hor_shift", 0, 14148959232670991301),
+            ("train/img_001.png", 0, "hue_rotation", 3, 8845485036436651910),
+            // stem był zamieniany na małe litery, katalogi pomijane
+            ("train/cat/CAT.png", 42, "mirror", 0, 6937311600622872891),
+        ];
+        for (path, seed, transform, variant, expected) in cases {
+            let actual = derive_seed(SeedScheme::V1, Path::new(path), seed, transform, variant);
+            assert_eq!(actual, expected, "{path} {transform} {variant}");
+        }
+    }
+
+    #[test]
+    fn v1_variant_zero_has_no_index() {
+        let mut h = Hasher::new();
+        h.update(&42u64.to_le_bytes());
+        h.update(b"cat");
+        h.update(b"mirror");
+        let expected = u64::from_le_bytes(h.finalize().as_bytes()[..8].try_into().unwrap());
+
+        assert_eq!(derive_seed(SeedScheme::V1, Path::new("cat.png"), 42, "mirror", 0), expected);
+        assert_ne!(derive_seed(SeedScheme::V1, Path::new("cat.png"), 42, "mirror", 1), expected);
+    }
+
+    #[test]
+    fn v2_separates_paths() {
+        let seed = |scheme, path: &str| derive_seed(scheme, Path::new(path), 42, "mirror", 0);
+
+        assert_eq!(seed(SeedScheme::V1, "train/cat/001.png"), seed(SeedScheme::V1, "val/dog/001.png"));
+        assert_ne!(seed(SeedScheme::V2, "train/cat/001.png"), seed(SeedScheme::V2, "val/dog/001.png"));
+        // granice komponentów są częścią seeda
+        assert_ne!(seed(SeedScheme::V2, "a/bc.png"), seed(SeedScheme::V2, "ab/c.png"));
+        // wielkość liter i rozszerzenie też
+        assert_ne!(seed(SeedScheme::V2, "cat.png"), seed(SeedScheme::V2, "Cat.png"));
+        assert_ne!(seed(SeedScheme::V2, "cat.png"), seed(SeedScheme::V2, "cat.PNG"));
+        // ta sama ścieżka zapisana inaczej daje ten sam seed
+        assert_eq!(seed(SeedScheme::V2, "a/b.png"), seed(SeedScheme::V2, "./a//b.png"));
+    }
+
+    #[test]
+    fn v2_separates_transforms_and_variants() {
+        let path = Path::new("cat.png");
+        let base = derive_seed(SeedScheme::V2, path, 42, "mirror", 0);
+
+        assert_ne!(base, derive_seed(SeedScheme::V2, path, 42, "mirror", 1));
+        assert_ne!(base, derive_seed(SeedScheme::V2, path, 42, "flip", 0));
+        assert_ne!(base, derive_seed(SeedScheme::V2, path, 43, "mirror", 0));
+    }
+}
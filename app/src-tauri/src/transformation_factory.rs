@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use image::{DynamicImage, ImageResult};
+use serde::Serialize;
+use std::{collections::HashMap, sync::OnceLock};
 
 use crate::transformations::*;
 
@@ -6,12 +8,29 @@ type TransformationFactoryFn = fn() -> Box<dyn ImageTransformation>;
 
 pub struct TransformationFactory {
     registry: HashMap<String, TransformationFactoryFn>,
+    // kolejność rejestracji, w takiej kolejności UI pokazuje transformacje
+    names: Vec<String>,
+}
+
+/// A registered transformation as the UI lists it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformationDescriptor {
+    pub id: String,
+    pub name: &'static str,
+    pub category: Category,
+    pub description: &'static str,
+    pub parameters: Vec<ParameterSpec>,
+    pub random: bool,
+    // nakładana na każde wyjście zamiast tworzyć własne
+    pub always: bool,
 }
 
 impl TransformationFactory {
     pub fn new() -> Self {
         let mut factory = TransformationFactory {
             registry: HashMap::new(),
+            names: Vec::new(),
         };
         factory.register::<ShiftH>("hor_shift");
         factory.register::<ShiftV>("ver_shift");
@@ -29,10 +48,51 @@ impl TransformationFactory {
         factory
     }
 
+    /// One factory for lookups that don't need their own, e.g. splitting a selection.
+    pub fn shared() -> &'static TransformationFactory {
+        static SHARED: OnceLock<TransformationFactory> = OnceLock::new();
+        SHARED.get_or_init(TransformationFactory::new)
+    }
+
     pub fn create(&self, name: &str) -> Option<Box<dyn ImageTransformation>> {
         self.registry.get(name).map(|constructor| constructor())
     }
 
+    /// Applies `name` with recorded parameters instead of sampling new ones.
+    /// `None` when the transformation no longer exists.
+    pub fn replay(&self, name: &str, img: &DynamicImage, params: &Params) -> Option<ImageResult<DynamicImage>> {
+        self.create(name).map(|transformation| transformation.apply_with(img, params))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.registry.contains_key(name)
+    }
+
+    /// Whether `name` is applied to every output, see `ImageTransformation::always`.
+    pub fn is_always(&self, name: &str) -> bool {
+        self.create(name).is_some_and(|transformation| transformation.always())
+    }
+
+    pub fn describe_all(&self) -> Vec<TransformationDescriptor> {
+        self.names
+            .iter()
+            .filter_map(|id| {
+                let transformation = self.create(id)?;
+                let info = transformation.info();
+                let parameters = transformation.parameters();
+                Some(TransformationDescriptor {
+                    id: id.clone(),
+                    name: info.name,
+                    category: info.category,
+                    description: info.description,
+                    random: !parameters.is_empty(),
+                    always: transformation.always(),
+                    parameters,
+                })
+            })
+            .collect()
+    }
+
     fn register<T: 'static + ImageTransformation + Default>(&mut self, name: &str) {
         fn ctor<T: 'static + ImageTransformation + Default>() -> Box<dyn ImageTransformation> {
             Box::new(T::default())
@@ -40,5 +100,6 @@ impl TransformationFactory {
 
         let constructor: TransformationFactoryFn = ctor::<T>;
         self.registry.insert(name.to_string(), constructor);
+        self.names.push(name.to_string());
     }
 }
@@ -1,29 +1,109 @@
+use image::error::{ParameterError, ParameterErrorKind};
 use image::*;
 use palette::{FromColor, Hsl, Srgb};
 use rand::rngs::StdRng;
 use rand::Rng;
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 // Move
 #[derive(Default)]
 pub struct ShiftV;
 impl ImageTransformation for ShiftV {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Vertical shift",
+            category: Category::Geometric,
+            description: "Wraps the image around vertically by 10–30% of its height.",
+        }
+    }
+
+    fn always(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "shift",
+                description: "Rows moved down, wrapping around",
+                unit: "px",
+                kind: ValueKind::Integer,
+                min: Some(0.0),
+                max: None,
+            },
+        ]
+    }
+
+    fn sample(&self, img: &DynamicImage, rng: &mut StdRng) -> Params {
         let shift = sample_shift(img.height(), rng, 0.10, 0.30);
         let shift = if rng.gen_bool(0.5) { shift } else { (img.height() - shift) % img.height() };
 
+        params([("shift", shift as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        let shift = param(params, "shift")? as u32;
         Ok(shift_image(img, shift, ShiftAxis::Vertical))
     }
+
+    fn scale(&self, params: &Params, from: (u32, u32), to: (u32, u32)) -> Params {
+        scale_shift(params, from.1, to.1)
+    }
 }
 
 #[derive(Default)]
 pub struct ShiftH;
 impl ImageTransformation for ShiftH {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Horizontal shift",
+            category: Category::Geometric,
+            description: "Wraps the image around horizontally by 10–30% of its width.",
+        }
+    }
+
+    fn always(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "shift",
+                description: "Columns moved right, wrapping around",
+                unit: "px",
+                kind: ValueKind::Integer,
+                min: Some(0.0),
+                max: None,
+            },
+        ]
+    }
+
+    fn sample(&self, img: &DynamicImage, rng: &mut StdRng) -> Params {
         let shift = sample_shift(img.width(), rng, 0.10, 0.30);
         let shift = if rng.gen_bool(0.5) { shift } else { (img.width() - shift) % img.width() };
 
+        params([("shift", shift as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        let shift = param(params, "shift")? as u32;
         Ok(shift_image(img, shift, ShiftAxis::Horizontal))
     }
+
+    fn scale(&self, params: &Params, from: (u32, u32), to: (u32, u32)) -> Params {
+        scale_shift(params, from.0, to.0)
+    }
+}
+
+// Przesunięcie w pikselach przeliczone na inny rozmiar, z zawinięciem jak w shift_image
+fn scale_shift(params: &Params, from: u32, to: u32) -> Params {
+    let mut scaled = params.clone();
+    if let Some(shift) = scaled.get_mut("shift") {
+        *shift = (*shift * to as f64 / from.max(1) as f64).round() % to.max(1) as f64;
+    }
+    scaled
 }
 
 fn sample_shift(dim: u32, rng: &mut StdRng, frac_min: f32, frac_max: f32) -> u32 {
@@ -73,7 +153,15 @@ fn shift_image(img: &DynamicImage, shift_pixels: u32, axis: ShiftAxis) -> Dynami
 #[derive(Default)]
 pub struct Rotate90;
 impl ImageTransformation for Rotate90 {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Rotate 90°",
+            category: Category::Geometric,
+            description: "Rotates the image 90° clockwise.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.rotate90())
     }
 }
@@ -81,7 +169,15 @@ impl ImageTransformation for Rotate90 {
 #[derive(Default)]
 pub struct Rotate180;
 impl ImageTransformation for Rotate180 {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Rotate 180°",
+            category: Category::Geometric,
+            description: "Rotates the image 180°.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.rotate180())
     }
 }
@@ -89,7 +185,15 @@ impl ImageTransformation for Rotate180 {
 #[derive(Default)]
 pub struct Rotate270;
 impl ImageTransformation for Rotate270 {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Rotate 270°",
+            category: Category::Geometric,
+            description: "Rotates the image 270° clockwise.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.rotate270())
     }
 }
@@ -98,7 +202,15 @@ impl ImageTransformation for Rotate270 {
 #[derive(Default)]
 pub struct FlipH;
 impl ImageTransformation for FlipH {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Mirror",
+            category: Category::Geometric,
+            description: "Mirrors the image left to right.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.fliph())
     }
 }
@@ -106,7 +218,15 @@ impl ImageTransformation for FlipH {
 #[derive(Default)]
 pub struct FlipV;
 impl ImageTransformation for FlipV {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Flip",
+            category: Category::Geometric,
+            description: "Flips the image upside down.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.flipv())
     }
 }
@@ -115,7 +235,28 @@ impl ImageTransformation for FlipV {
 #[derive(Default)]
 pub struct HueRotate;
 impl ImageTransformation for HueRotate {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Hue rotation",
+            category: Category::Colour,
+            description: "Rotates hues by 10–60° in either direction.",
+        }
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "degrees",
+                description: "Hue rotation",
+                unit: "°",
+                kind: ValueKind::Integer,
+                min: Some(-60.0),
+                max: Some(60.0),
+            },
+        ]
+    }
+
+    fn sample(&self, _: &DynamicImage, rng: &mut StdRng) -> Params {
         let min_deg = 10.0;
         let max_deg = 60.0;
 
@@ -125,7 +266,11 @@ impl ImageTransformation for HueRotate {
             deg = sign * min_deg;
         }
 
-        Ok(img.huerotate(deg.round() as i32))
+        params([("degrees", deg.round() as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        Ok(img.huerotate(param(params, "degrees")? as i32))
     }
 }
 
@@ -142,7 +287,28 @@ fn sample_triangular(rng: &mut StdRng, min: f32, mode: f32, max: f32) -> f32 {
 #[derive(Default)]
 pub struct Saturate;
 impl ImageTransformation for Saturate {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Saturation shift",
+            category: Category::Colour,
+            description: "Scales saturation up or down, as far as the image's mean saturation allows.",
+        }
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "factor",
+                description: "Saturation multiplier",
+                unit: "×",
+                kind: ValueKind::Float,
+                min: Some(0.2),
+                max: Some(1.8),
+            },
+        ]
+    }
+
+    fn sample(&self, img: &DynamicImage, rng: &mut StdRng) -> Params {
         let stats = compute_stats(img);
 
         let room_up = (1.0 - stats.mean_sat).max(0.0);
@@ -164,7 +330,11 @@ impl ImageTransformation for Saturate {
             }
         }
 
-        Ok(adjust_saturation_factor(img, f))
+        params([("factor", f as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        Ok(adjust_saturation_factor(img, param(params, "factor")? as f32))
     }
 }
 
@@ -186,14 +356,35 @@ fn adjust_saturation_factor(img: &DynamicImage, factor: f32) -> DynamicImage {
 #[derive(Default)]
 pub struct Brighten;
 impl ImageTransformation for Brighten {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Brightness shift",
+            category: Category::Colour,
+            description: "Adds a brightness delta scaled to the image's mean luminance.",
+        }
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "delta",
+                description: "Added to every channel",
+                unit: "levels",
+                kind: ValueKind::Integer,
+                min: Some(-255.0),
+                max: Some(255.0),
+            },
+        ]
+    }
+
+    fn sample(&self, img: &DynamicImage, rng: &mut StdRng) -> Params {
         let stats = compute_stats(img);
 
         let max_up = (255.0 * (1.0 - stats.mean_luma)).clamp(10.0, 255.0);
         let max_dn = (255.0 * stats.mean_luma).clamp(10.0, 255.0);
 
-        let min = -max_dn as f32;
-        let max =  max_up as f32;
+        let min = -max_dn;
+        let max =  max_up;
 
         let mut delta = sample_triangular(rng, min, 0.0, max);
 
@@ -205,14 +396,39 @@ impl ImageTransformation for Brighten {
         }
 
         delta = delta.clamp(min, max);
-        Ok(img.brighten(delta.round() as i32))
+        params([("delta", delta.round() as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        Ok(img.brighten(param(params, "delta")? as i32))
     }
 }
 
 #[derive(Default)]
 pub struct Contrast;
 impl ImageTransformation for Contrast {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Contrast shift",
+            category: Category::Colour,
+            description: "Raises or lowers contrast, less so for images far from mid-grey.",
+        }
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        vec![
+            ParameterSpec {
+                name: "contrast",
+                description: "Contrast change",
+                unit: "%",
+                kind: ValueKind::Float,
+                min: Some(-50.0),
+                max: Some(50.0),
+            },
+        ]
+    }
+
+    fn sample(&self, img: &DynamicImage, rng: &mut StdRng) -> Params {
         let stats = compute_stats(img);
         let center_dist = (stats.mean_luma - 0.5).abs();
 
@@ -229,7 +445,11 @@ impl ImageTransformation for Contrast {
         }
 
         c = c.clamp(-max_dec, max_inc);
-        Ok(img.adjust_contrast(c))
+        params([("contrast", c as f64)])
+    }
+
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage> {
+        Ok(img.adjust_contrast(param(params, "contrast")? as f32))
     }
 }
 
@@ -237,7 +457,15 @@ impl ImageTransformation for Contrast {
 #[derive(Default)]
 pub struct Grayscale;
 impl ImageTransformation for Grayscale {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Grayscale",
+            category: Category::Filter,
+            description: "Converts the image to grayscale.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         Ok(img.grayscale())
     }
 }
@@ -245,7 +473,15 @@ impl ImageTransformation for Grayscale {
 #[derive(Default)]
 pub struct Invert;
 impl ImageTransformation for Invert {
-    fn apply(&self, img: &DynamicImage, _: &mut StdRng) -> ImageResult<DynamicImage> {
+    fn info(&self) -> TransformationInfo {
+        TransformationInfo {
+            name: "Invert colors",
+            category: Category::Filter,
+            description: "Inverts every color channel.",
+        }
+    }
+
+    fn apply_with(&self, img: &DynamicImage, _: &Params) -> ImageResult<DynamicImage> {
         // Operation is in-place, that's why it's cloned
         let mut img_clone = img.clone();
         img_clone.invert();
@@ -253,8 +489,88 @@ impl ImageTransformation for Invert {
     }
 }
 
+/// Parameter values sampled for one application, e.g. `{"degrees": -25}` for hue rotation.
+pub type Params = BTreeMap<String, f64>;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Geometric,
+    Colour,
+    Filter,
+}
+
+/// How a transformation is presented in the UI.
+pub struct TransformationInfo {
+    pub name: &'static str,
+    pub category: Category,
+    pub description: &'static str,
+}
+
+/// One value `sample` draws, under the same name it has in `Params`, with the range it's drawn from.
+#[derive(Clone, Debug, Serialize)]
+pub struct ParameterSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub unit: &'static str,
+    pub kind: ValueKind,
+    // None, gdy granica zależy od obrazu, np. przesunięcie jest mniejsze od szerokości
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind {
+    Integer,
+    Float,
+}
+
 pub trait ImageTransformation {
-    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<DynamicImage>;
+    fn info(&self) -> TransformationInfo;
+
+    /// Applied to every output before the other transformations instead of producing outputs of its own.
+    fn always(&self) -> bool {
+        false
+    }
+
+    /// Parameters drawn by `sample`. Empty for deterministic transformations.
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        Vec::new()
+    }
+
+    /// Draws the random parameters for one application. Deterministic transformations have none.
+    fn sample(&self, _img: &DynamicImage, _rng: &mut StdRng) -> Params {
+        Params::new()
+    }
+
+    /// Applies the transformation with exactly these parameters, e.g. recorded by an earlier run.
+    fn apply_with(&self, img: &DynamicImage, params: &Params) -> ImageResult<DynamicImage>;
+
+    /// `params` sampled on an image of size `from`, adjusted to give the same result on one of size `to`.
+    /// Only parameters measured in pixels change.
+    fn scale(&self, params: &Params, _from: (u32, u32), _to: (u32, u32)) -> Params {
+        params.clone()
+    }
+
+    fn apply(&self, img: &DynamicImage, rng: &mut StdRng) -> ImageResult<(DynamicImage, Params)> {
+        let params = self.sample(img, rng);
+        Ok((self.apply_with(img, &params)?, params))
+    }
+}
+
+fn params<const N: usize>(values: [(&str, f64); N]) -> Params {
+    values.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+fn param(params: &Params, name: &str) -> ImageResult<f64> {
+    params.get(name).copied().ok_or_else(|| {
+        ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(format!(
+            "missing parameter '{name}'"
+        ))))
+    })
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -275,7 +591,7 @@ fn compute_stats(img: &DynamicImage) -> ImageStats {
         let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
         let hsl = Hsl::from_color(Srgb::new(r, g, b));
         sum_luma += luma;
-        sum_sat += hsl.saturation.max(0.0).min(1.0);
+        sum_sat += hsl.saturation.clamp(0.0, 1.0);
         count += 1;
     }
     if count == 0 {
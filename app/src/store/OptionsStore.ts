@@ -0,0 +1,30 @@
+import { writable } from 'svelte/store';
+
+export type TransformRule = {
+    pattern?: string;
+    class?: string;
+    only?: string[];
+    enable?: string[];
+    disable?: string[];
+};
+
+export type ConflictPolicy = 'overwrite' | 'skip' | 'rename' | 'fail';
+
+export const options = writable({
+    variantsPerImage: 1,
+    targetTotal: null as number | null,
+    balance: null as { targetCount?: number; ratio?: number } | null,
+    rules: [] as TransformRule[],
+    failFast: false,
+    threads: null as number | null,
+    chunkSize: 8,
+    adaptiveChunks: false,
+    maxConcurrentReads: null as number | null,
+    maxConcurrentWrites: null as number | null,
+    memoryBudgetMb: null as number | null,
+    writeIntoInput: false,
+    onConflict: 'overwrite' as ConflictPolicy,
+    seeding: 'v2' as 'v1' | 'v2',
+    report: null as { samples: number } | null,
+    split: null as { train: number; val: number; test: number; duplicateThreshold?: number } | null,
+});
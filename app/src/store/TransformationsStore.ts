@@ -1,17 +1,36 @@
 import { writable } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
 
-export const transformations = writable([
-    { id: 'hor_shift', name: 'Horizontal shift*', checked: false },
-    { id: 'ver_shift', name: 'Vertical shift*', checked: false },
-    { id: 'rotate90', name: 'Rotate 90°', checked: false },
-    { id: 'rotate180', name: 'Rotate 180°', checked: false },
-    { id: 'rotate270', name: 'Rotate 270°', checked: false },
-    { id: 'mirror', name: 'Mirror', checked: false },
-    { id: 'flip', name: 'Flip', checked: false },
-    { id: 'hue_rotation', name: 'Hue rotation', checked: false },
-    { id: 'saturation', name: 'Saturation shift', checked: false },
-    { id: 'brightness', name: 'Brightness shift', checked: false },
-    { id: 'contrast', name: 'Contrast shift', checked: false },
-    { id: 'grayscale', name: 'Grayscale', checked: false },
-    { id: 'invert', name: 'Invert colors', checked: false},
-]);
+export type Category = 'geometric' | 'colour' | 'filter';
+
+export type ParameterSpec = {
+    name: string;
+    description: string;
+    unit: string;
+    kind: 'integer' | 'float';
+    // brak, gdy granica zależy od obrazu
+    min?: number;
+    max?: number;
+};
+
+export type TransformationInfo = {
+    id: string;
+    name: string;
+    category: Category;
+    description: string;
+    parameters: ParameterSpec[];
+    random: boolean;
+    always: boolean;
+};
+
+export type TransformationOption = TransformationInfo & { checked: boolean };
+
+export const transformations = writable<TransformationOption[]>([]);
+
+// Lista pochodzi z backendu, więc nowa transformacja nie wymaga zmian w UI
+export async function loadTransformations(): Promise<TransformationOption[]> {
+    const listed = await invoke<TransformationInfo[]>('list_transformations');
+    const options = listed.map(info => ({ ...info, checked: false }));
+    transformations.set(options);
+    return options;
+}